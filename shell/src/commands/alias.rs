@@ -0,0 +1,137 @@
+use crate::commands::builtin::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// Quote a value with single quotes so it can be pasted back.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Whether `name` can be an alias: no blanks, quotes, `/`, `$` or `=`.
+fn is_valid_alias(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\' | '`' | '/' | '$' | '='))
+}
+
+/// `alias` lists every alias, `alias name` shows one and `alias name=value`
+/// defines one.
+pub fn aliass(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    if args.is_empty() {
+        for (name, value) in &shell.aliases {
+            let _ = writeln!(out, "alias {}={}", name, quote(value));
+        }
+        return 0;
+    }
+
+    let mut status = 0;
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if is_valid_alias(name) => {
+                shell.aliases.insert(name.to_string(), value.to_string());
+            }
+            Some(_) => {
+                let _ = writeln!(err, "alias: `{}': invalid alias name", arg);
+                status = 1;
+            }
+            None => match shell.aliases.get(arg) {
+                Some(value) => {
+                    let _ = writeln!(out, "alias {}={}", arg, quote(value));
+                }
+                None => {
+                    let _ = writeln!(err, "alias: {}: not found", arg);
+                    status = 1;
+                }
+            },
+        }
+    }
+    status
+}
+
+/// `unalias name...` removes aliases, `unalias -a` all of them.
+pub fn unaliass(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    if args.is_empty() {
+        let _ = writeln!(err, "unalias: usage: {}", Unalias.usage());
+        return 2;
+    }
+    if args[0] == "-a" {
+        shell.aliases.clear();
+        return 0;
+    }
+
+    let mut status = 0;
+    for name in args {
+        if shell.aliases.remove(name).is_none() {
+            let _ = writeln!(err, "unalias: {}: not found", name);
+            status = 1;
+        }
+    }
+    status
+}
+
+pub struct Alias;
+
+impl Builtin for Alias {
+    fn name(&self) -> &'static str {
+        "alias"
+    }
+
+    fn usage(&self) -> &'static str {
+        "alias [name[=value] ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Define aliases, or show them."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        aliass(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Unalias;
+
+impl Builtin for Unalias {
+    fn name(&self) -> &'static str {
+        "unalias"
+    }
+
+    fn usage(&self) -> &'static str {
+        "unalias [-a] name ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Remove aliases, or all of them with -a."
+    }
+
+    fn flags(&self) -> &'static str {
+        "a"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        unaliass(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(shell: &mut Shell, args: &[&str]) -> (i32, String, String) {
+        capture(|out, err| aliass(shell, out, err, &words(args)))
+    }
+
+    #[test]
+    fn lists_aliases_quoted_by_name() {
+        let mut shell = Shell::default();
+        alias(&mut shell, &["ll=ls -l", "q=echo 'x'"]);
+        assert_eq!(alias(&mut shell, &[]), (0, "alias ll='ls -l'\nalias q='echo '\\''x'\\'''\n".to_string(), String::new()));
+        assert_eq!(alias(&mut shell, &["ll"]), (0, "alias ll='ls -l'\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn unknown_and_invalid_names() {
+        let mut shell = Shell::default();
+        assert_eq!(alias(&mut shell, &["nope"]), (1, String::new(), "alias: nope: not found\n".to_string()));
+        assert_eq!(alias(&mut shell, &["a/b=c"]), (1, String::new(), "alias: `a/b=c': invalid alias name\n".to_string()));
+    }
+}
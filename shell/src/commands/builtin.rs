@@ -0,0 +1,136 @@
+use crate::commands::alias::*;
+use crate::commands::cat::*;
+use crate::commands::cd::*;
+use crate::commands::clear::*;
+use crate::commands::cp::*;
+use crate::commands::echo::*;
+use crate::commands::env::*;
+use crate::commands::exit::*;
+use crate::commands::export::*;
+use crate::commands::function::*;
+use crate::commands::help::*;
+use crate::commands::history::*;
+use crate::commands::jobs::*;
+use crate::commands::loop_control::*;
+use crate::commands::ls::*;
+use crate::commands::mkdir::*;
+use crate::commands::mv::*;
+use crate::commands::pwd::*;
+use crate::commands::rm::*;
+use crate::commands::source::*;
+use crate::commands::trap::*;
+use crate::commands::unset::*;
+use crate::variables::shell::*;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// What a builtin runs with.
+pub struct Context<'a> {
+    // the whole command line, `argv[0]` being the name it was called by
+    pub argv: &'a [String],
+    // the arguments after the name
+    pub args: &'a [String],
+    // for builtins that `check_flags`: the arguments split into
+    // single-letter flags and operands; for the others no flags and every
+    // argument an operand
+    pub flags: Vec<String>,
+    pub operands: Vec<String>,
+    pub stdin: &'a mut dyn Read,
+    pub stdout: &'a mut dyn Write,
+    pub stderr: &'a mut dyn Write,
+    // variables (the environment), aliases, functions and jobs
+    pub shell: &'a mut Shell,
+    // the current directory when it started, by `$PWD` when the directory
+    // is gone, or neither
+    pub cwd: Option<PathBuf>,
+}
+
+/// Run a builtin's function the way a test checks it: with `out` and `err`
+/// captured, for the status and what went to each.
+#[cfg(test)]
+pub fn capture(run: impl FnOnce(&mut dyn Write, &mut dyn Write) -> i32) -> (i32, String, String) {
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let status = run(&mut out, &mut err);
+    (status, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+/// Arguments as a command line has them.
+#[cfg(test)]
+pub fn words(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+/// A path under the temporary directory for a test to make a file or
+/// directory at, with whatever an earlier run left there gone.
+#[cfg(test)]
+pub fn scratch(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("0-shell-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// A command that runs inside the shell instead of being looked up on PATH.
+pub trait Builtin: Sync {
+    fn name(&self) -> &'static str;
+
+    /// How to call it, for `help` and usage errors.
+    fn usage(&self) -> &'static str;
+
+    /// What it does, in a sentence.
+    fn summary(&self) -> &'static str;
+
+    /// The single-letter flags it understands, for completion.
+    fn flags(&self) -> &'static str {
+        ""
+    }
+
+    /// Whether flags other than `flags` are an error caught before `run`,
+    /// which then finds them split out in the context. The others read
+    /// their arguments themselves.
+    fn check_flags(&self) -> bool {
+        false
+    }
+
+    /// Do its work and return the exit status.
+    fn run(&self, ctx: &mut Context) -> i32;
+}
+
+/// Every builtin, in the order `help` lists them.
+pub static BUILTINS: &[&dyn Builtin] = &[
+    &Alias,
+    &Bg,
+    &Break,
+    &Cat,
+    &Cd,
+    &Clear,
+    &Continue,
+    &Cp,
+    &Echo,
+    &Env,
+    &Exit,
+    &Export,
+    &Fg,
+    &Help,
+    &HistoryBuiltin,
+    &JobsBuiltin,
+    &Kill,
+    &Local,
+    &Ls,
+    &Mkdir,
+    &Mv,
+    &Pwd,
+    &Return,
+    &Rm,
+    &Source("source"),
+    &Source("."),
+    &Trap,
+    &Type,
+    &Unalias,
+    &Unset,
+    &Wait,
+];
+
+pub fn find_builtin(name: &str) -> Option<&'static dyn Builtin> {
+    BUILTINS.iter().find(|b| b.name() == name).copied()
+}
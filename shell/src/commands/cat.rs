@@ -1,31 +1,120 @@
-use std::io;
+use crate::commands::builtin::*;
+use crate::exec::signal::*;
+use std::io::{self, Read, Write};
 use std::fs;
 use std::path::Path;
 
-pub fn catfile(args: &[&str]) -> io::Result<()> {
+/// Like `io::copy`, but stops quietly on Ctrl-C instead of retrying the
+/// interrupted read.
+fn copy(reader: &mut (impl Read + ?Sized), writer: &mut (impl Write + ?Sized)) -> io::Result<()> {
+    let mut buf = [0u8; 8192];
+    while !interrupted() {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+    }
+    writer.flush()
+}
+
+/// Copy each file in turn to `out`, or `input` when there are none. `-` is
+/// `input` too.
+pub fn catfile(input: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
     if args.is_empty() {
-        let mut line = String::new();
-        loop {
-            line.clear();
-            let n = io::stdin().read_line(&mut line)?;
-            if n == 0 {
-                break;
-            }
-            print!("{}", line);
+        // stdin may be a terminal, a pipe or a redirected file; copy it as bytes
+        if let Err(e) = copy(input, out) {
+            let _ = writeln!(err, "cat: {}", e);
+            return 1;
         }
-        return Ok(());
+        return 0;
     }
 
-    for &file in args {
-        let path = Path::new(file);
-        if path.is_dir() {
-             eprintln!("'{}' is a directory", file);
-        return Ok(());
+    let mut status = 0;
+    for file in args {
+        if interrupted() {
+            break;
         }
+        let copied = if file == "-" {
+            copy(input, out)
+        } else {
+            let path = Path::new(file);
+            if path.is_dir() {
+                let _ = writeln!(err, "cat: {}: Is a directory", file);
+                status = 1;
+                continue;
+            }
+            fs::File::open(path).and_then(|mut file| copy(&mut file, out))
+        };
+        if let Err(e) = copied {
+            let _ = writeln!(err, "cat: {}: {}", file, e);
+            status = 1;
+        }
+    }
+    status
+}
+
+pub struct Cat;
+
+impl Builtin for Cat {
+    fn name(&self) -> &'static str {
+        "cat"
+    }
+
+    fn usage(&self) -> &'static str {
+        "cat [file ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Print files, or what comes in on stdin, one after the other."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        catfile(ctx.stdin, ctx.stdout, ctx.stderr, &ctx.operands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut file = fs::File::open(path)?;
-        io::copy(&mut file, &mut io::stdout())?;
+    fn cat(input: &[u8], args: &[&str]) -> (i32, String, String) {
+        capture(|out, err| catfile(&mut &input[..], out, err, &words(args)))
     }
 
-    Ok(())
+    #[test]
+    fn copies_input_without_files() {
+        assert_eq!(cat(b"one\ntwo\n", &[]), (0, "one\ntwo\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn dash_is_the_input() {
+        let path = scratch("cat");
+        fs::write(&path, "file\n").unwrap();
+        let file = path.to_str().unwrap();
+        let result = cat(b"stdin\n", &[file, "-", file]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result, (0, "file\nstdin\nfile\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn missing_file_is_reported_and_the_rest_printed() {
+        let (status, out, err) = cat(b"in", &["/nonexistent/0-shell", "-"]);
+        assert_eq!(status, 1);
+        assert_eq!(out, "in");
+        assert_eq!(err, "cat: /nonexistent/0-shell: No such file or directory (os error 2)\n");
+    }
+
+    #[test]
+    fn directory_is_reported_and_the_rest_printed() {
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        assert_eq!(cat(b"in", &[dir, "-"]), (1, "in".to_string(), format!("cat: {}: Is a directory\n", dir)));
+    }
 }
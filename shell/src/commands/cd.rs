@@ -1,32 +1,79 @@
+use crate::commands::builtin::*;
 use std::env;
 use std::path::Path;
+use std::io::Write;
 
-pub fn cdd(args: &[String]) {
+pub fn cdd(err: &mut dyn Write, args: &[String]) -> i32 {
     if args.is_empty() {
         let home_dir = env::var("HOME").or_else(|_| env::var("USERPROFILE"));
         match home_dir {
             Ok(path) => {
                 if let Err(e) = env::set_current_dir(Path::new(&path)) {
-                    eprintln!("cd: {}", e);
+                    let _ = writeln!(err, "cd: {}", e);
+                    return 1;
                 }
             }
-            Err(_) => eprintln!("cd: cannot find home directory"),
-        }
-    } else if args[0] == "~" {
-        let home_dir = env::var("HOME").or_else(|_| env::var("USERPROFILE"));
-        match home_dir {
-            Ok(path) => {
-                if let Err(e) = env::set_current_dir(Path::new(&path)) {
-                    eprintln!("cd: {}", e);
-                }
+            Err(_) => {
+                let _ = writeln!(err, "cd: cannot find home directory");
+                return 1;
             }
-            Err(_) => eprintln!("cd: cannot find home directory"),
         }
     } else {
         let target = &args[0];
         if let Err(e) = env::set_current_dir(target) {
-            eprintln!("cd: {}: {}", e, target);
+            let _ = writeln!(err, "cd: {}: {}", e, target);
+            return 1;
+        }
+    }
+    0
+}
+//
+
+pub struct Cd;
+
+impl Builtin for Cd {
+    fn name(&self) -> &'static str {
+        "cd"
+    }
+
+    fn usage(&self) -> &'static str {
+        "cd [dir | -]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Change the current directory, to $HOME without a dir and back with -."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        let mut args = ctx.operands.clone();
+        // `cd -` goes back to the previous directory and says where it went
+        let back = args.len() == 1 && args[0] == "-";
+        if back {
+            match ctx.shell.get_var("OLDPWD") {
+                Some(dir) => args[0] = dir.to_string(),
+                None => {
+                    let _ = writeln!(ctx.stderr, "cd: OLDPWD not set");
+                    return 1;
+                }
+            }
+        }
+        let status = cdd(ctx.stderr, &args);
+        if back && status == 0 {
+            let _ = writeln!(ctx.stdout, "{}", args[0]);
+        }
+        // keep $PWD and $OLDPWD current for `~+` and `~-`
+        if status == 0 {
+            if let Some(old) = &ctx.cwd {
+                ctx.shell.set_var("OLDPWD", &old.display().to_string());
+            }
+            if let Ok(new) = env::current_dir() {
+                ctx.shell.set_var("PWD", &new.display().to_string());
+            }
         }
+        status
     }
 }
-//
\ No newline at end of file
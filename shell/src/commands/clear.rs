@@ -1,5 +1,39 @@
-pub fn clearaw() {
-    std::process::Command::new("clear")
-          .status()
-          .unwrap();
+use crate::commands::builtin::*;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Run `clear` for the escape codes that clear this terminal and write them
+/// to `out`.
+pub fn clearaw(out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    match Command::new("clear").stderr(Stdio::inherit()).output() {
+        Ok(output) => {
+            let _ = out.write_all(&output.stdout);
+            let _ = out.flush();
+            output.status.code().unwrap_or(1)
+        }
+        Err(e) => {
+            let _ = writeln!(err, "clear: {}", e);
+            1
+        }
+    }
+}
+
+pub struct Clear;
+
+impl Builtin for Clear {
+    fn name(&self) -> &'static str {
+        "clear"
+    }
+
+    fn usage(&self) -> &'static str {
+        "clear"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Clear the terminal screen."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        clearaw(ctx.stdout, ctx.stderr)
+    }
 }
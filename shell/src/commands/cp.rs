@@ -1,29 +1,58 @@
+use crate::commands::builtin::*;
 use std::fs;
-use std::io;
 use std::path::Path;
+use std::io::Write;
 
-pub fn cpp(args: &[String]) -> io::Result<()> {
+pub fn cpp(err: &mut dyn Write, args: &[String]) -> i32 {
     let src = Path::new(&args[0]);
     let dst = Path::new(&args[1]);
     if src == dst{
-        eprintln!("cp: '{}' and '{}' are the same file.",src.display(),dst.display());
-        return Ok(());
+        let _ = writeln!(err, "cp: '{}' and '{}' are the same file.",src.display(),dst.display());
+        return 1;
     }
     if src.is_dir() {
-        eprintln!("cp: cannot copy directories");
-        return Ok(());
+        let _ = writeln!(err, "cp: cannot copy directories");
+        return 1;
     }
 
     if !src.exists() {
-        eprintln!("cp: cannot stat '{}': No such file or directory" , src.display());
-        return Ok(());
+        let _ = writeln!(err, "cp: cannot stat '{}': No such file or directory" , src.display());
+        return 1;
     }
 
     match fs::copy(src, dst) {
-        Ok(_) => Ok(()),
+        Ok(_) => 0,
         Err(e) => {
-            eprintln!("cp: failed to copy '{}': {}", src.display(), e);
-            Ok(())
+            let _ = writeln!(err, "cp: failed to copy '{}': {}", src.display(), e);
+            1
         }
     }
 }
+
+pub struct Cp;
+
+impl Builtin for Cp {
+    fn name(&self) -> &'static str {
+        "cp"
+    }
+
+    fn usage(&self) -> &'static str {
+        "cp source dest"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Copy a file."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        if ctx.operands.len() < 2 {
+            let _ = writeln!(ctx.stderr, "cp: missing file operand");
+            return 1;
+        }
+        cpp(ctx.stderr, &ctx.operands)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::commands::builtin::*;
+use std::io::Write;
+
+/// Print the arguments separated by spaces. echo takes no options, so `-x`
+/// and `--` are printed as given.
+pub fn echoo(out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    match writeln!(out, "{}", args.join(" ")) {
+        Ok(()) => 0,
+        Err(e) => {
+            let _ = writeln!(err, "echo: write error: {}", e);
+            1
+        }
+    }
+}
+
+pub struct Echo;
+
+impl Builtin for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn usage(&self) -> &'static str {
+        "echo [arg ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Print the arguments separated by spaces."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        echoo(ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_arguments_as_given() {
+        let result = capture(|out, err| echoo(out, err, &words(&["a", "-n", "--", "b  c"])));
+        assert_eq!(result, (0, "a -n -- b  c\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn no_arguments_is_an_empty_line() {
+        assert_eq!(capture(|out, err| echoo(out, err, &[])), (0, "\n".to_string(), String::new()));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::commands::builtin::*;
+use crate::exec::external::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// `env` prints the exported variables to `out`. `env NAME=value cmd ...`
+/// is left to the real env program, which writes to the shell's own stdout
+/// and stderr rather than `out`.
+pub fn envv(shell: &mut Shell, out: &mut dyn Write, args: &[String]) -> i32 {
+    if !args.is_empty() {
+        return run_external(shell, "env", args);
+    }
+
+    for (name, var) in shell.vars.iter().filter(|(_, v)| v.exported) {
+        let _ = writeln!(out, "{}={}", name, var.value);
+    }
+    0
+}
+
+pub struct Env;
+
+impl Builtin for Env {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn usage(&self) -> &'static str {
+        "env [name=value ...] [command [arg ...]]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Print the exported variables, or run a command with more of them."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        envv(ctx.shell, ctx.stdout, ctx.args)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::commands::builtin::*;
+use crate::exec::trap::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// Run the EXIT trap with `$?` set to the status, then leave.
+fn leave(shell: &mut Shell, status: i32) -> ! {
+    shell.last_status = status;
+    run_exit_trap(shell);
+    shell.exit(status)
+}
+
+pub fn exitt(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    match args {
+        [] => leave(shell, shell.last_status),
+        [code] => match code.parse::<i64>() {
+            // like other shells only the low 8 bits reach the parent
+            Ok(n) => leave(shell, (n & 0xff) as i32),
+            Err(_) => {
+                let _ = writeln!(err, "exit: {}: numeric argument required", code);
+                leave(shell, 2)
+            }
+        },
+        _ => {
+            let _ = writeln!(err, "exit: too many arguments");
+            1
+        }
+    }
+}
+
+pub struct Exit;
+
+impl Builtin for Exit {
+    fn name(&self) -> &'static str {
+        "exit"
+    }
+
+    fn usage(&self) -> &'static str {
+        "exit [n]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Leave the shell with status n, or that of the last command."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        // in a pipeline stage this only ends that stage's process
+        exitt(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
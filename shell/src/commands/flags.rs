@@ -0,0 +1,20 @@
+/// Split a builtin's arguments into single-letter flags and operands.
+///
+/// `-la` is the same as `-l -a` and flags may come after operands, but `-`
+/// alone is an operand and so is everything after `--`.
+pub fn split_flags(args: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut flags = Vec::new();
+    let mut operands = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            operands.extend(args.cloned());
+            break;
+        }
+        match arg.strip_prefix('-') {
+            Some(letters) if !letters.is_empty() => flags.extend(letters.chars().map(String::from)),
+            _ => operands.push(arg.clone()),
+        }
+    }
+    (flags, operands)
+}
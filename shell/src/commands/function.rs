@@ -0,0 +1,94 @@
+use crate::commands::builtin::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// `local NAME[=value]...`: variables that only live until the running
+/// function returns. Without a value the variable starts out unset.
+pub fn locall(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    let mut status = 0;
+    for arg in args {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        if !is_valid_name(name) {
+            let _ = writeln!(err, "local: `{}': not a valid identifier", arg);
+            status = 1;
+            continue;
+        }
+        if !shell.make_local(name) {
+            let _ = writeln!(err, "local: can only be used in a function");
+            return 1;
+        }
+        match value {
+            Some(value) => shell.set_var(name, value),
+            None => shell.unset_var(name),
+        }
+    }
+    status
+}
+
+/// `return [N]`: leave the running function or sourced file with status N,
+/// or with the status of the last command.
+pub fn returnn(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    if shell.locals.is_empty() && shell.source_depth == 0 {
+        let _ = writeln!(err, "return: can only `return' from a function or sourced script");
+        return 1;
+    }
+    let status = match args {
+        [] => shell.last_status,
+        [n] => match n.parse::<i64>() {
+            Ok(n) => (n & 0xff) as i32,
+            Err(_) => {
+                let _ = writeln!(err, "return: {}: numeric argument required", n);
+                2
+            }
+        },
+        _ => {
+            let _ = writeln!(err, "return: too many arguments");
+            return 1;
+        }
+    };
+    shell.flow = Flow::Return;
+    status
+}
+
+pub struct Local;
+
+impl Builtin for Local {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn usage(&self) -> &'static str {
+        "local name[=value] ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Make variables that only live until the running function returns."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        locall(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Return;
+
+impl Builtin for Return {
+    fn name(&self) -> &'static str {
+        "return"
+    }
+
+    fn usage(&self) -> &'static str {
+        "return [n]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Leave the running function or sourced file."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        returnn(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
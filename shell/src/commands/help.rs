@@ -0,0 +1,94 @@
+use crate::commands::builtin::*;
+use crate::exec::external::*;
+use crate::parsing::parser::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// `help` lists the builtins with what they do, `help name ...` how to call
+/// the ones named.
+pub fn helpp(out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    if args.is_empty() {
+        let width = BUILTINS.iter().map(|b| b.name().len()).max().unwrap_or(0);
+        for builtin in BUILTINS {
+            let _ = writeln!(out, "{:width$}  {}", builtin.name(), builtin.summary());
+        }
+        return 0;
+    }
+    let mut status = 0;
+    for name in args {
+        match find_builtin(name) {
+            Some(builtin) => {
+                let _ = writeln!(out, "{}: {}", builtin.name(), builtin.usage());
+                let _ = writeln!(out, "    {}", builtin.summary());
+            }
+            None => {
+                let _ = writeln!(err, "help: no help topics match `{}'.", name);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// `type name ...` says what each name runs as a command, looking in the
+/// same order the shell does.
+pub fn typee(shell: &Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let mut status = 0;
+    for name in args {
+        if RESERVED.contains(&name.as_str()) {
+            let _ = writeln!(out, "{} is a shell keyword", name);
+        } else if let Some(value) = shell.aliases.get(name) {
+            let _ = writeln!(out, "{} is aliased to `{}'", name, value);
+        } else if shell.functions.contains_key(name) {
+            let _ = writeln!(out, "{} is a function", name);
+        } else if find_builtin(name).is_some() {
+            let _ = writeln!(out, "{} is a shell builtin", name);
+        } else if let Some(path) = find_in_path(name).filter(|p| is_executable(p)) {
+            let _ = writeln!(out, "{} is {}", name, path.display());
+        } else {
+            let _ = writeln!(err, "type: {}: not found", name);
+            status = 1;
+        }
+    }
+    status
+}
+
+pub struct Help;
+
+impl Builtin for Help {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn usage(&self) -> &'static str {
+        "help [name ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "List the builtins, or show how to call the ones named."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        helpp(ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Type;
+
+impl Builtin for Type {
+    fn name(&self) -> &'static str {
+        "type"
+    }
+
+    fn usage(&self) -> &'static str {
+        "type name ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Say whether each name is a keyword, alias, function, builtin or file on PATH."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        typee(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::commands::builtin::*;
+use crate::exec::signal::*;
+use crate::history::store::*;
+use crate::variables::shell::*;
+use chrono::TimeZone;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// When an entry was typed, formatted with `HISTTIMEFORMAT` (strftime
+/// style). Empty when the variable is unset or the time is unknown.
+fn timestamp(shell: &Shell, entry: &HistoryEntry) -> String {
+    let Some(format) = shell.get_var("HISTTIMEFORMAT") else {
+        return String::new();
+    };
+    let Some(time) = chrono::Local.timestamp_opt(entry.time, 0).single().filter(|_| entry.time > 0) else {
+        return String::new();
+    };
+    // an unknown `%` sequence makes formatting fail rather than panic here
+    let mut out = String::new();
+    match write!(out, "{}", time.format(format)) {
+        Ok(()) => out,
+        Err(_) => String::new(),
+    }
+}
+
+/// Report a failure to write the history file; the history in memory has
+/// changed either way.
+fn save(shell: &Shell, err: &mut dyn Write) -> i32 {
+    let config = HistoryConfig::from_shell(shell);
+    match shell.history.save(&config) {
+        Ok(()) => 0,
+        Err(e) => {
+            let path = config.file.unwrap_or_default();
+            let _ = writeln!(err, "history: {}: {}", path.display(), e);
+            1
+        }
+    }
+}
+
+/// `history` lists the commands typed so far, `history n` the last `n` of
+/// them. `-c` clears the history and `-d n` deletes entry `n` (counted from
+/// the end when negative).
+pub fn historyy(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("-c") => {
+            shell.history.clear();
+            save(shell, err)
+        }
+        Some("-d") => {
+            let Some(offset) = args.get(1) else {
+                let _ = writeln!(err, "history: -d: option requires an argument");
+                let _ = writeln!(err, "history: usage: {}", HistoryBuiltin.usage());
+                return 2;
+            };
+            let len = shell.history.entries().len();
+            let base = shell.history.base();
+            let n = match offset.parse::<i64>() {
+                // `-1` is the last entry
+                Ok(n) if n < 0 => (base + len) as i64 + n,
+                Ok(n) => n,
+                Err(_) => -1,
+            };
+            if n < 0 || !shell.history.delete(n as usize) {
+                let _ = writeln!(err, "history: {}: history position out of range", offset);
+                return 1;
+            }
+            save(shell, err)
+        }
+        Some(arg) if arg.starts_with('-') && arg.len() > 1 => {
+            let _ = writeln!(err, "history: {}: invalid option", arg);
+            let _ = writeln!(err, "history: usage: {}", HistoryBuiltin.usage());
+            2
+        }
+        count => {
+            let entries = shell.history.entries();
+            let count = match count.map(|c| c.parse::<usize>()) {
+                None => entries.len(),
+                Some(Ok(n)) => n.min(entries.len()),
+                Some(Err(_)) => {
+                    let _ = writeln!(err, "history: {}: numeric argument required", args[0]);
+                    return 1;
+                }
+            };
+            let skip = entries.len() - count;
+            for (idx, entry) in entries.iter().enumerate().skip(skip) {
+                if interrupted() {
+                    break;
+                }
+                let _ = writeln!(out, "{:>5}  {}{}", shell.history.base() + idx, timestamp(shell, entry), entry.line);
+            }
+            0
+        }
+    }
+}
+
+pub struct HistoryBuiltin;
+
+impl Builtin for HistoryBuiltin {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn usage(&self) -> &'static str {
+        "history [-c] [-d offset] [n]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Show the commands typed so far, or clear or delete entries."
+    }
+
+    fn flags(&self) -> &'static str {
+        "cd"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        historyy(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
@@ -0,0 +1,381 @@
+use crate::commands::builtin::*;
+use crate::commands::flags::*;
+use crate::exec::job::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// `jobs` lists the jobs with their state, `-l` with their process group
+/// and `-p` only the process group. Operands pick which jobs.
+pub fn jobss(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let (flags, specs) = split_flags(args);
+    let mut long = false;
+    let mut pids_only = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "l" => long = true,
+            "p" => pids_only = true,
+            _ => {
+                let _ = writeln!(err, "jobs: -{}: invalid option", flag);
+                let _ = writeln!(err, "jobs: usage: {}", JobsBuiltin.usage());
+                return 2;
+            }
+        }
+    }
+
+    shell.jobs.update();
+    let mut status = 0;
+    let ids: Vec<usize> = if specs.is_empty() {
+        shell.jobs.list.iter().map(|j| j.id).collect()
+    } else {
+        specs
+            .iter()
+            .filter_map(|spec| match shell.jobs.find(spec) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    let _ = writeln!(err, "jobs: {}", e);
+                    status = 1;
+                    None
+                }
+            })
+            .collect()
+    };
+    for job in shell.jobs.list.iter().filter(|j| ids.contains(&j.id)) {
+        if pids_only {
+            let _ = writeln!(out, "{}", job.pgid);
+        } else {
+            let _ = writeln!(out, "{}", shell.jobs.format(job, long));
+        }
+    }
+    shell.jobs.reported();
+    status
+}
+
+/// The job `fg` or `bg` works on, reporting what is wrong for `name`.
+fn pick_job(shell: &Shell, err: &mut dyn Write, name: &str, spec: Option<&String>) -> Option<usize> {
+    if !shell.jobs.control {
+        let _ = writeln!(err, "{}: no job control", name);
+        return None;
+    }
+    match shell.jobs.find(spec.map_or("", |s| s.as_str())) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            let _ = writeln!(err, "{}: {}", name, e);
+            None
+        }
+    }
+}
+
+/// `fg [job]` brings a job to the foreground, the current one by default,
+/// and waits for it.
+pub fn fgg(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let Some(id) = pick_job(shell, err, "fg", args.first()) else {
+        return 1;
+    };
+    let Some(job) = shell.jobs.remove(id) else {
+        return 1;
+    };
+    let _ = writeln!(out, "{}", job.text);
+    shell.jobs.foreground = job.text.clone();
+    resume_foreground(shell, job)
+}
+
+/// `bg [job ...]` lets stopped jobs go on in the background.
+pub fn bgg(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let specs: Vec<Option<&String>> = if args.is_empty() { vec![None] } else { args.iter().map(Some).collect() };
+    let mut status = 0;
+    for spec in specs {
+        let Some(id) = pick_job(shell, err, "bg", spec) else {
+            status = 1;
+            continue;
+        };
+        let mark = shell.jobs.mark(id);
+        let Some(job) = shell.jobs.get(id) else {
+            continue;
+        };
+        if job.state() == JobState::Running {
+            let _ = writeln!(err, "bg: job {} already in background", id);
+            continue;
+        }
+        match job.resume() {
+            Ok(()) => {
+                let _ = writeln!(out, "[{}]{} {} &", id, mark, job.text);
+            }
+            Err(e) => {
+                let _ = writeln!(err, "bg: {}", e);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// `wait` waits for every running job; `wait job|pid ...` for those and
+/// returns the status of the last one.
+pub fn waitt(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    if args.is_empty() {
+        for job in &mut shell.jobs.list {
+            job.wait();
+        }
+        shell.jobs.reported();
+        return 0;
+    }
+
+    let mut status = 0;
+    for arg in args {
+        let id = if arg.starts_with('%') {
+            match shell.jobs.find(arg) {
+                Ok(id) => id,
+                Err(e) => {
+                    let _ = writeln!(err, "wait: {}", e);
+                    status = 127;
+                    continue;
+                }
+            }
+        } else {
+            let Ok(pid) = arg.parse() else {
+                let _ = writeln!(err, "wait: `{}': not a pid or valid job spec", arg);
+                status = 2;
+                continue;
+            };
+            match shell.jobs.with_pid(pid) {
+                Some(id) => id,
+                None => {
+                    let _ = writeln!(err, "wait: pid {} is not a child of this shell", pid);
+                    status = 127;
+                    continue;
+                }
+            }
+        };
+        let Some(job) = shell.jobs.get(id) else {
+            continue;
+        };
+        job.wait();
+        status = match job.state() {
+            JobState::Done(code) => {
+                shell.jobs.remove(id);
+                code
+            }
+            JobState::Stopped(sig) => 128 + sig,
+            JobState::Running => 0,
+        };
+    }
+    status
+}
+
+/// `kill -l` lists the signals, `kill -l n` names one (also from an exit
+/// status like 143).
+pub fn list_signals(out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    if args.is_empty() {
+        for (idx, (num, name, _)) in SIGNALS.iter().enumerate() {
+            let entry = format!("{:2}) SIG{}", num, name);
+            if idx % 5 == 4 || idx == SIGNALS.len() - 1 {
+                let _ = writeln!(out, "{}", entry);
+            } else {
+                let _ = write!(out, "{:<14}", entry);
+            }
+        }
+        return 0;
+    }
+    let mut status = 0;
+    for arg in args {
+        let found = match arg.parse::<i32>() {
+            Ok(n) => {
+                let n = if n > 128 { n - 128 } else { n };
+                SIGNALS.iter().find(|(num, _, _)| *num == n).map(|(_, name, _)| name.to_string())
+            }
+            Err(_) => signal_number(arg).map(|n| n.to_string()),
+        };
+        match found {
+            Some(answer) => {
+                let _ = writeln!(out, "{}", answer);
+            }
+            None => {
+                let _ = writeln!(err, "kill: {}: invalid signal specification", arg);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// `kill [-s sig | -n num | -sig] pid|job ...` sends a signal, `TERM` by
+/// default, to processes and jobs. A stopped job sent `TERM` or `HUP` also
+/// gets `CONT` so it can act on it.
+pub fn killl(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let mut sig = libc::SIGTERM;
+    let mut rest = args;
+    match args.first().map(String::as_str) {
+        Some("-l" | "-L") => return list_signals(out, err, &args[1..]),
+        Some("-s" | "-n") => {
+            let Some(name) = args.get(1) else {
+                let _ = writeln!(err, "kill: {}: option requires an argument", args[0]);
+                return 2;
+            };
+            match signal_number(name) {
+                Some(n) => sig = n,
+                None => {
+                    let _ = writeln!(err, "kill: {}: invalid signal specification", name);
+                    return 1;
+                }
+            }
+            rest = &args[2..];
+        }
+        Some("--") => rest = &args[1..],
+        Some(arg) if arg.len() > 1 && arg.starts_with('-') => {
+            match signal_number(&arg[1..]) {
+                Some(n) => sig = n,
+                None => {
+                    let _ = writeln!(err, "kill: {}: invalid signal specification", &arg[1..]);
+                    return 1;
+                }
+            }
+            rest = &args[1..];
+        }
+        _ => {}
+    }
+    if rest.is_empty() {
+        let _ = writeln!(err, "kill: usage: {}", Kill.usage());
+        return 2;
+    }
+
+    let mut status = 0;
+    for target in rest {
+        if target.starts_with('%') {
+            let job = shell.jobs.find(target).map(|id| shell.jobs.get(id).map(|j| j.clone()));
+            let sent = match job {
+                Ok(Some(job)) => {
+                    let sent = job.signal(sig).map_err(|e| format!("{}: {}", target, e));
+                    if matches!(job.state(), JobState::Stopped(_)) && matches!(sig, libc::SIGTERM | libc::SIGHUP) {
+                        let _ = job.signal(libc::SIGCONT);
+                    }
+                    sent
+                }
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = sent {
+                let _ = writeln!(err, "kill: {}", e);
+                status = 1;
+            }
+            continue;
+        }
+        let Ok(pid) = target.parse::<i32>() else {
+            let _ = writeln!(err, "kill: {}: arguments must be process or job IDs", target);
+            status = 1;
+            continue;
+        };
+        if unsafe { libc::kill(pid, sig) } == -1 {
+            let _ = writeln!(err, "kill: ({}) - {}", pid, std::io::Error::last_os_error());
+            status = 1;
+        }
+    }
+    status
+}
+
+pub struct JobsBuiltin;
+
+impl Builtin for JobsBuiltin {
+    fn name(&self) -> &'static str {
+        "jobs"
+    }
+
+    fn usage(&self) -> &'static str {
+        "jobs [-lp] [jobspec ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "List the jobs and what they are doing."
+    }
+
+    fn flags(&self) -> &'static str {
+        "lp"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        jobss(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Fg;
+
+impl Builtin for Fg {
+    fn name(&self) -> &'static str {
+        "fg"
+    }
+
+    fn usage(&self) -> &'static str {
+        "fg [jobspec]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Bring a job to the foreground and wait for it."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        fgg(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Bg;
+
+impl Builtin for Bg {
+    fn name(&self) -> &'static str {
+        "bg"
+    }
+
+    fn usage(&self) -> &'static str {
+        "bg [jobspec ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Let stopped jobs go on in the background."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        bgg(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Wait;
+
+impl Builtin for Wait {
+    fn name(&self) -> &'static str {
+        "wait"
+    }
+
+    fn usage(&self) -> &'static str {
+        "wait [jobspec | pid ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Wait for jobs to finish and return the status of the last."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        waitt(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Kill;
+
+impl Builtin for Kill {
+    fn name(&self) -> &'static str {
+        "kill"
+    }
+
+    fn usage(&self) -> &'static str {
+        "kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Send a signal to processes or jobs, or list the signals."
+    }
+
+    fn flags(&self) -> &'static str {
+        "lns"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        killl(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::commands::builtin::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// Parse the optional loop count of `break`/`continue`, capped at the number
+/// of enclosing loops. None (after printing why) when it is not usable.
+fn loop_count(shell: &Shell, err: &mut dyn Write, name: &str, args: &[String]) -> Option<usize> {
+    let n = match args {
+        [] => 1,
+        [n] => match n.parse::<i64>() {
+            Ok(n) if n >= 1 => n as usize,
+            Ok(_) => {
+                let _ = writeln!(err, "{}: {}: loop count out of range", name, n);
+                return None;
+            }
+            Err(_) => {
+                let _ = writeln!(err, "{}: {}: numeric argument required", name, n);
+                return None;
+            }
+        },
+        _ => {
+            let _ = writeln!(err, "{}: too many arguments", name);
+            return None;
+        }
+    };
+    if shell.loop_depth == 0 {
+        let _ = writeln!(err, "{}: only meaningful in a `for', `while', or `until' loop", name);
+        return Some(0);
+    }
+    Some(n.min(shell.loop_depth))
+}
+
+pub fn breakk(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    match loop_count(shell, err, "break", args) {
+        Some(0) => 0,
+        Some(n) => {
+            shell.flow = Flow::Break(n);
+            0
+        }
+        None => 1,
+    }
+}
+
+pub fn continuee(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    match loop_count(shell, err, "continue", args) {
+        Some(0) => 0,
+        Some(n) => {
+            shell.flow = Flow::Continue(n);
+            0
+        }
+        None => 1,
+    }
+}
+
+pub struct Break;
+
+impl Builtin for Break {
+    fn name(&self) -> &'static str {
+        "break"
+    }
+
+    fn usage(&self) -> &'static str {
+        "break [n]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Leave the innermost loop, or n of them."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        breakk(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
+
+pub struct Continue;
+
+impl Builtin for Continue {
+    fn name(&self) -> &'static str {
+        "continue"
+    }
+
+    fn usage(&self) -> &'static str {
+        "continue [n]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Go on with the next round of the innermost loop, or of the nth."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        continuee(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
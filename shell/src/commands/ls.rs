@@ -1,8 +1,11 @@
+use crate::commands::builtin::*;
+use crate::editor::terminal::*;
+use crate::exec::signal::*;
 use chrono::{Datelike, TimeZone};
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::io::{IsTerminal, Write};
 use std::{cmp::Ordering, fs, io, path::Path};
 use users::{get_group_by_gid, get_user_by_uid};
-use terminal_size::{Width, terminal_size};
 
 /// ANSI color codes
 const BLUE: &str = "\x1b[34m";
@@ -18,11 +21,15 @@ struct LsConfig {
     show_all: bool,
     long_format: bool,
     classify: bool,
+    // the listing goes to a terminal: colours, and names in columns
+    tty: bool,
 }
 
-pub fn lss(flags: &[String], args: &[String]) {
-    let mut config = LsConfig::default();
-    let mut errors = Vec::new();
+/// List `args` (the current directory without any) to `out`, relative ones
+/// from `cwd`. `tty` says whether `out` is a terminal, which gets colours
+/// and columns.
+pub fn lss(out: &mut dyn Write, err: &mut dyn Write, tty: bool, cwd: &Path, flags: &[String], args: &[String]) -> i32 {
+    let mut config = LsConfig { tty, ..LsConfig::default() };
 
     // Parse flags
     for flag in flags {
@@ -30,7 +37,8 @@ pub fn lss(flags: &[String], args: &[String]) {
             "a" => config.show_all = true,
             "l" => config.long_format = true,
             "F" => config.classify = true,
-            _ => errors.push(format!("ls: invalid option -- '{}'", flag)),
+            // the others are turned away before `ls` runs
+            _ => {}
         }
     }
 
@@ -40,29 +48,42 @@ pub fn lss(flags: &[String], args: &[String]) {
         args.iter().map(|s| s.as_str()).collect()
     };
 
-    // Print errors first
-    for error in &errors {
-        eprintln!("{}", error);
-    }
+    let mut status = 0;
 
     let mut output = String::with_capacity(4096);
-    list_targets(&targets, &config, &mut output);
+    if !list_targets(err, cwd, &targets, &config, &mut output) {
+        status = 2;
+    }
+    // half a listing is no use after Ctrl-C
+    if interrupted() {
+        return 128 + libc::SIGINT;
+    }
 
+    if output.is_empty() {
+        return status;
+    }
     if output.ends_with('\n') {
         output.pop();
     }
-    println!("{}", output);
+    let _ = writeln!(out, "{}", output);
+    status
 }
 
-fn list_targets(targets: &[&str], config: &LsConfig, output: &mut String) {
+/// Returns false if any target could not be listed.
+fn list_targets(err: &mut dyn Write, cwd: &Path, targets: &[&str], config: &LsConfig, output: &mut String) -> bool {
     let show_header = targets.len() > 1;
+    let mut ok = true;
 
     for (idx, target) in targets.iter().enumerate() {
+        if interrupted() {
+            break;
+        }
         if idx > 0 {
             output.push('\n');
         }
 
-        let path = Path::new(target);
+        // found from `cwd`, shown as given
+        let path = &cwd.join(target);
 
         if show_header {
             output.push_str(target);
@@ -72,24 +93,19 @@ fn list_targets(targets: &[&str], config: &LsConfig, output: &mut String) {
         // Handle single file or symlink
         if path.is_file() || (path.is_symlink() && !path.is_dir()) {
             if let Err(e) = list_file(path, target, config, output) {
-                output.push_str("ls: ");
-                output.push_str(target);
-                output.push_str(": ");
-                output.push_str(&e.to_string());
-                output.push('\n');
+                let _ = writeln!(err, "ls: {}: {}", target, e);
+                ok = false;
             }
             continue;
         }
 
         // Handle directory
         if let Err(e) = list_directory(path, config, output) {
-            output.push_str("ls: ");
-            output.push_str(target);
-            output.push_str(": ");
-            output.push_str(&e.to_string());
-            output.push('\n');
+            let _ = writeln!(err, "ls: {}: {}", target, e);
+            ok = false;
         }
     }
+    ok
 }
 
 fn list_file(path: &Path, name: &str, config: &LsConfig, output: &mut String) -> io::Result<()> {
@@ -114,7 +130,7 @@ fn list_file(path: &Path, name: &str, config: &LsConfig, output: &mut String) ->
         name.to_string()
     };
 
-    let colored_name = colorize(&display_name, &meta);
+    let colored_name = colorize(&display_name, &meta, config.tty);
 
     if config.long_format {
         output.push_str(&long_format_line(path, &meta, &colored_name, config.classify));
@@ -139,6 +155,9 @@ fn list_directory(path: &Path, config: &LsConfig, output: &mut String) -> io::Re
     }
 
     for entry in fs::read_dir(path)? {
+        if interrupted() {
+            return Ok(());
+        }
         let entry = entry?;
         let name = entry.file_name().to_string_lossy().into_owned();
 
@@ -155,13 +174,16 @@ fn list_directory(path: &Path, config: &LsConfig, output: &mut String) -> io::Re
     items.sort_unstable_by(|a, b| ls_cmp(&a.0, &b.0));
 
     if config.long_format {
-        let total_blocks: u64 = items.iter().map(|(_, _, m)| m.blocks() as u64).sum();
+        let total_blocks: u64 = items.iter().map(|(_, _, m)| m.blocks()).sum();
         output.push_str("total ");
-        output.push_str(&((total_blocks + 1) / 2).to_string());
+        output.push_str(&total_blocks.div_ceil(2).to_string());
         output.push('\n');
 
         for (name, path, meta) in &items {
-            let colored = colorize(name, meta);
+            if interrupted() {
+                break;
+            }
+            let colored = colorize(name, meta, config.tty);
             output.push_str(&long_format_line(path, meta, &colored, config.classify));
             output.push('\n');
         }
@@ -174,18 +196,23 @@ fn list_directory(path: &Path, config: &LsConfig, output: &mut String) -> io::Re
                 if config.classify {
                     n.push_str(suffix_for(meta, config.classify));
                 }
-                colorize(&n, meta)
+                colorize(&n, meta, config.tty)
             })
             .collect();
 
-        format_columns(&short_names, output);
+        format_columns(&short_names, config.tty.then(width), output);
     }
 
     Ok(())
 }
 
 /// Apply ANSI colors similar to `ls --color`
-fn colorize(name: &str, meta: &fs::Metadata) -> String {
+fn colorize(name: &str, meta: &fs::Metadata, tty: bool) -> String {
+    // like `--color=auto`, no escape codes in pipes, files or `$(ls)`
+    if !tty {
+        return name.to_string();
+    }
+
     let ft = meta.file_type();
     let mode = meta.mode();
 
@@ -204,21 +231,15 @@ fn colorize(name: &str, meta: &fs::Metadata) -> String {
     }
 }
 
-// Add this to your Cargo.toml dependencies:
-// terminal_size = "0.3"
-
-fn format_columns(names: &[String], output: &mut String) {
+/// Lay out `names` in columns that fit `width`, or one per line without a
+/// width (not a terminal).
+pub fn format_columns(names: &[String], width: Option<usize>, output: &mut String) {
     if names.is_empty() {
         return;
     }
 
     const MIN_GAP: usize = 2;
-    const FALLBACK_WIDTH: usize = 80;
-
-    // Get actual terminal width
-    let term_width = terminal_size()
-        .map(|(Width(w), _)| w as usize)
-        .unwrap_or(FALLBACK_WIDTH);
+    let term_width = width.unwrap_or(0);
 
     // Strip ANSI codes for width calculation
     fn visible_width(s: &str) -> usize {
@@ -240,8 +261,8 @@ fn format_columns(names: &[String], output: &mut String) {
     let widths: Vec<usize> = names.iter().map(|s| visible_width(s)).collect();
     let max_width = *widths.iter().max().unwrap_or(&0);
     
-    // If even one item won't fit, print one per line
-    if max_width >= term_width {
+    // If even one item won't fit, or the output goes to a pipe, print one per line
+    if max_width >= term_width || width.is_none() {
         for name in names {
             output.push_str(name);
             output.push('\n');
@@ -252,7 +273,7 @@ fn format_columns(names: &[String], output: &mut String) {
     // Calculate optimal number of columns
     let col_width = max_width + MIN_GAP;
     let num_cols = (term_width / col_width).max(1);
-    let num_rows = (names.len() + num_cols - 1) / num_cols;
+    let num_rows = names.len().div_ceil(num_cols);
 
     // Print in column-major order
     for row in 0..num_rows {
@@ -278,7 +299,7 @@ fn format_columns(names: &[String], output: &mut String) {
 
 
 #[inline]
-fn ls_cmp(a: &str, b: &str) -> Ordering {
+pub fn ls_cmp(a: &str, b: &str) -> Ordering {
     let a_dot = a.starts_with('.');
     let b_dot = b.starts_with('.');
 
@@ -290,7 +311,7 @@ fn ls_cmp(a: &str, b: &str) -> Ordering {
 }
 
 #[inline]
-fn suffix_for<'a>(meta: &'a fs::Metadata, classify: bool) -> &'a str {
+fn suffix_for(meta: &fs::Metadata, classify: bool) -> &str {
     if !classify {
         return "";
     }
@@ -405,4 +426,113 @@ fn long_format_line(path: &Path, meta: &fs::Metadata, name: &str, classify: bool
         "{}{} {:>2} {:<8} {:<8} {} {} {}",
         file_type, perms, nlink, user, group, size_or_dev, date_str, display_name
     )
-}
\ No newline at end of file
+}
+
+pub struct Ls;
+
+impl Builtin for Ls {
+    fn name(&self) -> &'static str {
+        "ls"
+    }
+
+    fn usage(&self) -> &'static str {
+        "ls [-alF] [file ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "List directory contents: -a all files, -l long format, -F type suffixes."
+    }
+
+    fn flags(&self) -> &'static str {
+        "alF"
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        // the handles are the shell's own, so its stdout says whether they are a terminal
+        let cwd = ctx.cwd.as_deref().unwrap_or(Path::new("."));
+        lss(ctx.stdout, ctx.stderr, io::stdout().is_terminal(), cwd, &ctx.flags, &ctx.operands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fresh directory with a hidden file, two plain files and a
+    /// subdirectory.
+    fn sample_dir(name: &str) -> PathBuf {
+        let dir = scratch(&format!("ls-{}", name));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for file in ["B.txt", "a", ".hidden"] {
+            fs::write(dir.join(file), "x").unwrap();
+        }
+        dir
+    }
+
+    /// `ls` run in `cwd`.
+    fn ls(flags: &str, cwd: &Path, args: &[&str]) -> (i32, String, String) {
+        let flags: Vec<String> = flags.chars().map(String::from).collect();
+        capture(|out, err| lss(out, err, false, cwd, &flags, &words(args)))
+    }
+
+    #[test]
+    fn one_name_per_line_without_a_terminal() {
+        let dir = sample_dir("plain");
+        let result = ls("", &dir, &[]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result, (0, "a\nB.txt\nsub\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn all_and_classify() {
+        let dir = sample_dir("all");
+        let result = ls("aF", &dir, &[]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result.1, "./\n../\n.hidden\na\nB.txt\nsub/\n");
+    }
+
+    #[test]
+    fn long_format_has_a_total() {
+        let dir = sample_dir("long");
+        let (status, out, _) = ls("l", &dir, &[]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(status, 0);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("total "));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[3].starts_with('d') && lines[3].ends_with(" sub"));
+    }
+
+    #[test]
+    fn relative_targets_are_found_from_cwd() {
+        let dir = sample_dir("relative");
+        let result = ls("", &dir, &["sub", "a"]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result, (0, "sub:\n\na:\na\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn missing_target_is_an_error() {
+        let (status, out, err) = ls("", Path::new("/"), &["nonexistent/0-shell"]);
+        assert_eq!(status, 2);
+        assert!(out.is_empty());
+        assert!(err.starts_with("ls: nonexistent/0-shell: "));
+    }
+
+    #[test]
+    fn columns_fit_the_width() {
+        let names = words(&["a", "bb", "ccc", "d"]);
+        let mut output = String::new();
+        format_columns(&names, Some(10), &mut output);
+        assert_eq!(output, "a    ccc\nbb   d\n");
+
+        output.clear();
+        format_columns(&names, None, &mut output);
+        assert_eq!(output, "a\nbb\nccc\nd\n");
+    }
+}
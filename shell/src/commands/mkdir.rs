@@ -1,12 +1,43 @@
+use crate::commands::builtin::*;
 use std::fs;
-use std::io;
+use std::io::Write;
 
-pub fn mkdirr(args: &[String]) -> io::Result<()> {
+pub fn mkdirr(err: &mut dyn Write, args: &[String]) -> i32 {
+    let mut status = 0;
     for dir in args {
         if let Err(e) = fs::create_dir(dir) {
-            eprintln!("mkdir: cannot create directory '{}': {}", dir, e);
+            let _ = writeln!(err, "mkdir: cannot create directory '{}': {}", dir, e);
+            status = 1;
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    status
+}
+
+pub struct Mkdir;
+
+impl Builtin for Mkdir {
+    fn name(&self) -> &'static str {
+        "mkdir"
+    }
+
+    fn usage(&self) -> &'static str {
+        "mkdir dir ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Create directories."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        if ctx.operands.is_empty() {
+            let _ = writeln!(ctx.stderr, "mkdir: missing operand");
+            return 1;
+        }
+        mkdirr(ctx.stderr, &ctx.operands)
+    }
+}
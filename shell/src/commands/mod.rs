@@ -1,8 +1,24 @@
 pub mod clear;
 pub mod cat;
 pub mod cd;
+pub mod env;
+pub mod exit;
+pub mod export;
 pub mod ls;
 pub mod mkdir;
 pub mod cp;
 pub mod rm;
-pub mod mv;
\ No newline at end of file
+pub mod mv;
+pub mod unset;
+pub mod flags;
+pub mod loop_control;
+pub mod function;
+pub mod alias;
+pub mod source;
+pub mod history;
+pub mod jobs;
+pub mod trap;
+pub mod builtin;
+pub mod echo;
+pub mod pwd;
+pub mod help;
\ No newline at end of file
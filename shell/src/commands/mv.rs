@@ -1,35 +1,72 @@
+use crate::commands::builtin::*;
+use crate::exec::signal::*;
 use std::fs;
 use std::path::Path;
+use std::io::Write;
 
-pub fn mvv(args: &[String]) -> Result<(), String> {
+pub fn mvv(err: &mut dyn Write, args: &[String]) -> i32 {
     let sources = &args[..args.len() - 1];
     let dest = Path::new(&args[args.len() - 1]);
 
-    if sources.len() > 1 {
-        if !dest.is_dir() {
-            return Err(format!("mv: target {}: is not a directory", dest.display()));
-        }
+    if sources.len() > 1 && !dest.is_dir() {
+        let _ = writeln!(err, "mv: target {}: is not a directory", dest.display());
+        return 1;
     }
 
+    let mut status = 0;
+
     for src in sources {
+        if interrupted() {
+            break;
+        }
         let src_path = Path::new(src);
 
         if !src_path.exists() {
-            eprintln!("mv: cannot stat {}: No such file or directory", src);
+            let _ = writeln!(err, "mv: cannot stat {}: No such file or directory", src);
+            status = 1;
             continue;
         }
         
         let mut dest_path = dest.to_path_buf();
-        if dest.is_dir() {
-            if let Some(file_name) = src_path.file_name() {
-                dest_path.push(file_name);
-            }
+        if dest.is_dir()
+            && let Some(file_name) = src_path.file_name()
+        {
+            dest_path.push(file_name);
         }
 
-        if let Err(e) = fs::rename(&src_path, &dest_path) {
-            eprintln!("mv: cannot move {} to {}: {}", src, dest_path.display(), e);
+        if let Err(e) = fs::rename(src_path, &dest_path) {
+            let _ = writeln!(err, "mv: cannot move {} to {}: {}", src, dest_path.display(), e);
+            status = 1;
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    status
+}
+
+pub struct Mv;
+
+impl Builtin for Mv {
+    fn name(&self) -> &'static str {
+        "mv"
+    }
+
+    fn usage(&self) -> &'static str {
+        "mv source ... dest"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Move or rename files."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        if ctx.operands.len() < 2 {
+            let _ = writeln!(ctx.stderr, "mv: missing file operand");
+            return 1;
+        }
+        mvv(ctx.stderr, &ctx.operands)
+    }
+}
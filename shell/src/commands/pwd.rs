@@ -0,0 +1,40 @@
+use crate::commands::builtin::*;
+use std::io::Write;
+use std::path::Path;
+
+pub fn pwdd(out: &mut dyn Write, err: &mut dyn Write, cwd: Option<&Path>) -> i32 {
+    match cwd {
+        Some(path) => {
+            let _ = writeln!(out, "{}", path.display());
+            0
+        }
+        None => {
+            let _ = writeln!(err, "pwd: cannot find the current directory");
+            1
+        }
+    }
+}
+
+pub struct Pwd;
+
+impl Builtin for Pwd {
+    fn name(&self) -> &'static str {
+        "pwd"
+    }
+
+    fn usage(&self) -> &'static str {
+        "pwd"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Print the current directory."
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        pwdd(ctx.stdout, ctx.stderr, ctx.cwd.as_deref())
+    }
+}
@@ -1,28 +1,34 @@
+use crate::commands::builtin::*;
+use crate::exec::signal::*;
 use std::fs;
-use std::io;
 use std::path::Path;
+use std::io::Write;
 
-pub fn rm(flags: &[String], args: &[String]) -> io::Result<()> {
+pub fn rm(err: &mut dyn Write, flags: &[String], args: &[String]) -> i32 {
     if args.is_empty() {
-        eprintln!("rm: missing operand");
-        return Ok(());
+        let _ = writeln!(err, "rm: missing operand");
+        return 1;
     }
 
-    if !flags.is_empty() {
-        if flags.len() != 1 || flags[0] != "r" {
-            eprintln!("rm: only '-r' flag is supported");
-            return Ok(());
-        }
+    if !flags.is_empty() && (flags.len() != 1 || flags[0] != "r") {
+        let _ = writeln!(err, "rm: only '-r' flag is supported");
+        return 1;
     }
 
+    let mut status = 0;
+
     for target in args {
+        if interrupted() {
+            break;
+        }
         let path = Path::new(target);
 
         // use symlink_metadata so it doesn't follow links
         let meta = match fs::symlink_metadata(path) {
             Ok(m) => m,
             Err(_) => {
-                eprintln!("rm: cannot remove '{}': No such file or directory", target);
+                let _ = writeln!(err, "rm: cannot remove '{}': No such file or directory", target);
+                status = 1;
                 continue;
             }
         };
@@ -32,20 +38,51 @@ pub fn rm(flags: &[String], args: &[String]) -> io::Result<()> {
         if file_type.is_dir() && !file_type.is_symlink() {
             // directory (not a symlink)
             if flags.is_empty() {
-                eprintln!("rm: cannot remove '{}': is a directory", target);
+                let _ = writeln!(err, "rm: cannot remove '{}': is a directory", target);
+                status = 1;
                 continue;
             }
 
             if let Err(e) = fs::remove_dir_all(path) {
-                eprintln!("rm: failed to remove directory '{}': {}", target, e);
+                let _ = writeln!(err, "rm: failed to remove directory '{}': {}", target, e);
+                status = 1;
             }
         } else {
             // file or symlink
             if let Err(e) = fs::remove_file(path) {
-                eprintln!("rm: failed to remove file '{}': {}", target, e);
+                let _ = writeln!(err, "rm: failed to remove file '{}': {}", target, e);
+                status = 1;
             }
         }
     }
 
-    Ok(())
+    status
+}
+
+pub struct Rm;
+
+impl Builtin for Rm {
+    fn name(&self) -> &'static str {
+        "rm"
+    }
+
+    fn usage(&self) -> &'static str {
+        "rm [-r] file ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Remove files, and directories with -r."
+    }
+
+    fn flags(&self) -> &'static str {
+        "r"
+    }
+
+    fn check_flags(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        rm(ctx.stderr, &ctx.flags, &ctx.operands)
+    }
 }
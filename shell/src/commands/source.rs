@@ -0,0 +1,53 @@
+use crate::commands::builtin::*;
+use crate::exec::external::*;
+use crate::exec::script::*;
+use crate::variables::shell::*;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::io::Write;
+
+/// `source file [args]` and `. file [args]`: run a file in the current shell.
+/// A name without a slash is looked up on PATH first, then in the current
+/// directory.
+pub fn sourcee(shell: &mut Shell, err: &mut dyn Write, name: &str, args: &[String]) -> i32 {
+    let Some((file, params)) = args.split_first() else {
+        let _ = writeln!(err, "{}: filename argument required", name);
+        return 2;
+    };
+
+    let path = find_in_path(file).filter(|p| p.is_file()).unwrap_or_else(|| PathBuf::from(file));
+
+    match fs::read_to_string(&path) {
+        Ok(text) => run_sourced(shell, &text, params),
+        Err(e) => {
+            let msg = match e.kind() {
+                io::ErrorKind::NotFound => "No such file or directory".to_string(),
+                _ => e.to_string(),
+            };
+            let _ = writeln!(err, "{}: {}: {}", name, file, msg);
+            1
+        }
+    }
+}
+
+/// `source` and `.`, the same builtin under two names.
+pub struct Source(pub &'static str);
+
+impl Builtin for Source {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn usage(&self) -> &'static str {
+        if self.0 == "." { ". file [arg ...]" } else { "source file [arg ...]" }
+    }
+
+    fn summary(&self) -> &'static str {
+        "Run the commands of a file in the current shell."
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        sourcee(ctx.shell, ctx.stderr, &ctx.argv[0], ctx.args)
+    }
+}
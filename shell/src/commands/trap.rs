@@ -0,0 +1,133 @@
+use crate::commands::builtin::*;
+use crate::commands::alias::*;
+use crate::commands::jobs::*;
+use crate::exec::trap::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// Show trap commands the way they are set, all of them when `names` is
+/// empty.
+fn print_traps(shell: &Shell, out: &mut dyn Write, err: &mut dyn Write, names: &[String]) -> i32 {
+    let mut status = 0;
+    let conditions: Vec<TrapCondition> = if names.is_empty() {
+        shell.traps.actions.keys().copied().collect()
+    } else {
+        names
+            .iter()
+            .filter_map(|name| {
+                let condition = TrapCondition::parse(name);
+                if condition.is_none() {
+                    let _ = writeln!(err, "trap: {}: invalid signal specification", name);
+                    status = 1;
+                }
+                condition
+            })
+            .collect()
+    };
+    for condition in conditions {
+        if let Some(action) = shell.traps.actions.get(&condition) {
+            let _ = writeln!(out, "trap -- {} {}", quote(action), condition.name());
+        }
+    }
+    status
+}
+
+/// `trap action condition ...` runs `action` when a signal arrives, when a
+/// command fails (`ERR`) or when the shell exits (`EXIT`). An empty action
+/// ignores the signal and `-` puts back the default, as does giving only
+/// conditions. `trap` and `trap -p` list what is set, `trap -l` the
+/// signals.
+pub fn trapp(shell: &mut Shell, out: &mut dyn Write, err: &mut dyn Write, args: &[String]) -> i32 {
+    let mut args = args;
+    match args.first().map(String::as_str) {
+        None => return print_traps(shell, out, err, &[]),
+        Some("-p") => return print_traps(shell, out, err, &args[1..]),
+        Some("-l") => return list_signals(out, err, &[]),
+        Some("--") => args = &args[1..],
+        Some(arg) if arg.starts_with('-') && arg.len() > 1 => {
+            let _ = writeln!(err, "trap: {}: invalid option", arg);
+            let _ = writeln!(err, "trap: usage: {}", Trap.usage());
+            return 2;
+        }
+        _ => {}
+    }
+    let Some(first) = args.first() else {
+        return print_traps(shell, out, err, &[]);
+    };
+
+    // a lone condition, or a number first, means the conditions are reset
+    let (action, names) = if args.len() == 1 || first.parse::<u32>().is_ok() {
+        (None, args)
+    } else if first == "-" {
+        (None, &args[1..])
+    } else {
+        (Some(first.as_str()), &args[1..])
+    };
+    let mut status = 0;
+    for name in names {
+        match TrapCondition::parse(name) {
+            Some(condition) => set_trap(shell, condition, action),
+            None => {
+                let _ = writeln!(err, "trap: {}: invalid signal specification", name);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+pub struct Trap;
+
+impl Builtin for Trap {
+    fn name(&self) -> &'static str {
+        "trap"
+    }
+
+    fn usage(&self) -> &'static str {
+        "trap [-lp] [[action] condition ...]"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Run a command when a signal arrives, a command fails (ERR) or the shell exits (EXIT)."
+    }
+
+    fn flags(&self) -> &'static str {
+        "lp"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        trapp(ctx.shell, ctx.stdout, ctx.stderr, ctx.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trap(shell: &mut Shell, args: &[&str]) -> (i32, String, String) {
+        capture(|out, err| trapp(shell, out, err, &words(args)))
+    }
+
+    #[test]
+    fn lists_traps_the_way_they_are_set() {
+        let mut shell = Shell::default();
+        trap(&mut shell, &["echo 'bye'", "EXIT"]);
+        // a signal trap is listed without setting it, which would install a
+        // handler in the test process
+        shell.traps.actions.insert(TrapCondition::Signal(libc::SIGUSR1), String::new());
+        let listing = "trap -- 'echo '\\''bye'\\''' EXIT\ntrap -- '' SIGUSR1\n";
+        assert_eq!(trap(&mut shell, &["-p"]), (0, listing.to_string(), String::new()));
+        assert_eq!(trap(&mut shell, &[]), (0, listing.to_string(), String::new()));
+        assert_eq!(trap(&mut shell, &["-p", "exit"]), (0, "trap -- 'echo '\\''bye'\\''' EXIT\n".to_string(), String::new()));
+    }
+
+    #[test]
+    fn reset_and_invalid_conditions() {
+        let mut shell = Shell::default();
+        trap(&mut shell, &["echo x", "EXIT"]);
+        assert_eq!(trap(&mut shell, &["-", "EXIT"]).0, 0);
+        assert_eq!(trap(&mut shell, &["-p"]).1, "");
+        let (status, _, err) = trap(&mut shell, &["-p", "NOPE"]);
+        assert_eq!((status, err.as_str()), (1, "trap: NOPE: invalid signal specification\n"));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::commands::builtin::*;
+use crate::variables::shell::*;
+use std::io::Write;
+
+/// `unset [-f|-v] NAME...`: remove variables, or functions with `-f`.
+pub fn unsett(shell: &mut Shell, err: &mut dyn Write, args: &[String]) -> i32 {
+    let (functions, names) = match args.first().map(String::as_str) {
+        Some("-f") => (true, &args[1..]),
+        Some("-v") => (false, &args[1..]),
+        _ => (false, args),
+    };
+
+    let mut status = 0;
+    for name in names {
+        if functions {
+            shell.functions.remove(name);
+            continue;
+        }
+        if !is_valid_name(name) {
+            let _ = writeln!(err, "unset: `{}': not a valid identifier", name);
+            status = 1;
+            continue;
+        }
+        shell.unset_var(name);
+    }
+    status
+}
+
+pub struct Unset;
+
+impl Builtin for Unset {
+    fn name(&self) -> &'static str {
+        "unset"
+    }
+
+    fn usage(&self) -> &'static str {
+        "unset [-f | -v] name ..."
+    }
+
+    fn summary(&self) -> &'static str {
+        "Remove variables, or functions with -f."
+    }
+
+    fn flags(&self) -> &'static str {
+        "fv"
+    }
+
+    fn run(&self, ctx: &mut Context) -> i32 {
+        unsett(ctx.shell, ctx.stderr, ctx.args)
+    }
+}
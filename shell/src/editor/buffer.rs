@@ -0,0 +1,246 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Text being edited and the cursor in it. The cursor is an index into
+/// `chars`, so it always sits between two characters.
+#[derive(Debug, Clone, Default)]
+pub struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+/// Characters drawn on top of the one before them (combining accents and
+/// the like); the cursor moves over them together with it.
+fn is_zero_width(c: char) -> bool {
+    c != '\n' && c.width() == Some(0)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl LineBuffer {
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn chars(&self) -> &[char] {
+        &self.chars
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Replace the whole text and put the cursor at its end.
+    pub fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.chars.len());
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.insert(c);
+        }
+    }
+
+    /// Replace the text from `start` to the cursor, leaving the cursor
+    /// after the new text.
+    pub fn replace_before_cursor(&mut self, start: usize, text: &str) {
+        self.remove(start.min(self.cursor), self.cursor);
+        self.insert_str(text);
+    }
+
+    fn prev_boundary(&self, mut pos: usize) -> usize {
+        while pos > 0 {
+            pos -= 1;
+            if !is_zero_width(self.chars[pos]) {
+                break;
+            }
+        }
+        pos
+    }
+
+    fn next_boundary(&self, mut pos: usize) -> usize {
+        if pos < self.chars.len() {
+            pos += 1;
+        }
+        while pos < self.chars.len() && is_zero_width(self.chars[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Start of the line (of a multi-line text) the position is on.
+    fn line_start(&self, pos: usize) -> usize {
+        self.chars[..pos].iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self, pos: usize) -> usize {
+        self.chars[pos..].iter().position(|&c| c == '\n').map_or(self.chars.len(), |i| pos + i)
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.prev_boundary(self.cursor);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary(self.cursor);
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = self.line_start(self.cursor);
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.line_end(self.cursor);
+    }
+
+    /// Start of the word before the cursor (letters, digits and `_`).
+    fn word_start(&self) -> usize {
+        let mut pos = self.cursor;
+        while pos > 0 && !is_word_char(self.chars[pos - 1]) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word_char(self.chars[pos - 1]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// End of the word after the cursor.
+    fn word_end(&self) -> usize {
+        let mut pos = self.cursor;
+        while pos < self.chars.len() && !is_word_char(self.chars[pos]) {
+            pos += 1;
+        }
+        while pos < self.chars.len() && is_word_char(self.chars[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.word_start();
+    }
+
+    pub fn word_right(&mut self) {
+        self.cursor = self.word_end();
+    }
+
+    /// Whether the cursor is on the first (or last) line of the text.
+    pub fn on_first_line(&self) -> bool {
+        !self.chars[..self.cursor].contains(&'\n')
+    }
+
+    pub fn on_last_line(&self) -> bool {
+        !self.chars[self.cursor..].contains(&'\n')
+    }
+
+    /// Move to the line above or below, keeping the column where possible.
+    pub fn move_line(&mut self, up: bool) {
+        let start = self.line_start(self.cursor);
+        let column = self.cursor - start;
+        let target = if up {
+            if start == 0 {
+                return;
+            }
+            self.line_start(start - 1)
+        } else {
+            let end = self.line_end(self.cursor);
+            if end == self.chars.len() {
+                return;
+            }
+            end + 1
+        };
+        self.cursor = (target + column).min(self.line_end(target));
+    }
+
+    /// Remove the characters between `from` and `to` and return them.
+    fn remove(&mut self, from: usize, to: usize) -> String {
+        let removed: String = self.chars.drain(from..to).collect();
+        self.cursor = from;
+        removed
+    }
+
+    pub fn delete_back(&mut self) {
+        if self.cursor > 0 {
+            let from = self.prev_boundary(self.cursor);
+            self.remove(from, self.cursor);
+        }
+    }
+
+    pub fn delete_forward(&mut self) {
+        if self.cursor < self.chars.len() {
+            let to = self.next_boundary(self.cursor);
+            self.remove(self.cursor, to);
+        }
+    }
+
+    /// Ctrl-K: up to the end of the line, or the newline when already there.
+    pub fn kill_to_end(&mut self) -> String {
+        let mut end = self.line_end(self.cursor);
+        if end == self.cursor && end < self.chars.len() {
+            end += 1;
+        }
+        self.remove(self.cursor, end)
+    }
+
+    /// Ctrl-U: from the start of the line to the cursor.
+    pub fn kill_to_start(&mut self) -> String {
+        let start = self.line_start(self.cursor);
+        self.remove(start, self.cursor)
+    }
+
+    /// Ctrl-W: the whitespace separated word before the cursor.
+    pub fn kill_big_word_back(&mut self) -> String {
+        let mut pos = self.cursor;
+        while pos > 0 && self.chars[pos - 1].is_whitespace() {
+            pos -= 1;
+        }
+        while pos > 0 && !self.chars[pos - 1].is_whitespace() {
+            pos -= 1;
+        }
+        self.remove(pos, self.cursor)
+    }
+
+    /// Alt-Backspace: the word before the cursor.
+    pub fn kill_word_back(&mut self) -> String {
+        let start = self.word_start();
+        self.remove(start, self.cursor)
+    }
+
+    /// Alt-D: the word after the cursor.
+    pub fn kill_word_forward(&mut self) -> String {
+        let end = self.word_end();
+        self.remove(self.cursor, end)
+    }
+
+    /// Ctrl-T: swap the characters around the cursor, or the last two at
+    /// the end of the line.
+    pub fn transpose(&mut self) {
+        if self.chars.len() < 2 || self.cursor == 0 {
+            return;
+        }
+        if self.cursor == self.chars.len() || self.chars[self.cursor] == '\n' {
+            self.cursor -= 1;
+        }
+        if self.cursor == 0 || self.chars[self.cursor - 1] == '\n' || self.chars[self.cursor] == '\n' {
+            return;
+        }
+        self.chars.swap(self.cursor - 1, self.cursor);
+        self.cursor += 1;
+    }
+}
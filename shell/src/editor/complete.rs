@@ -0,0 +1,281 @@
+use crate::commands::builtin::*;
+use crate::exec::external::*;
+use crate::parsing::expand::*;
+use crate::variables::shell::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Words after which a new command starts, like after `;`.
+const COMMAND_PREFIXES: &[&str] = &["if", "then", "elif", "else", "while", "until", "do", "!", "{"];
+
+/// What Tab does to the text being edited.
+#[derive(Debug, Default)]
+pub struct Completion {
+    // char index where the word being completed starts
+    pub start: usize,
+    // what replaces the text from `start` to the cursor, when Tab can add
+    // something to the word
+    pub replacement: Option<String>,
+    // what to list when it cannot, because several things match
+    pub choices: Vec<String>,
+}
+
+/// The word the cursor is at the end of.
+#[derive(Debug, Default)]
+struct CurrentWord {
+    // char index where it starts
+    start: usize,
+    // its text with quotes and backslashes taken out
+    value: String,
+    // the quote still open at the cursor
+    quote: Option<char>,
+    // it starts with an unquoted `~`
+    tilde: bool,
+    // the words before it in its command; empty when it is the command name
+    before: Vec<String>,
+    // it follows `<` or `>`, so it names a file
+    redirect: bool,
+}
+
+/// Find the word ending at the end of `text`, following the shell's quoting
+/// closely enough to know where words and commands start.
+fn current_word(text: &str) -> CurrentWord {
+    let mut word = CurrentWord::default();
+    // whether the word so far had any quotes, so `''` still counts as a word
+    let mut quoted = false;
+    let mut chars = text.chars().enumerate().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        match (c, word.quote) {
+            ('\\', Some('\'')) => word.value.push(c),
+            ('\\', _) => {
+                if let Some((_, next)) = chars.next() {
+                    word.value.push(next);
+                }
+                quoted = true;
+            }
+            ('\'' | '"', None) => {
+                word.quote = Some(c);
+                quoted = true;
+            }
+            (c, Some(q)) if c == q => word.quote = None,
+            (_, Some(_)) => word.value.push(c),
+            (' ' | '\t' | '\n' | ';' | '|' | '&' | '(' | ')' | '<' | '>', None) => {
+                let value = std::mem::take(&mut word.value);
+                if !value.is_empty() || quoted {
+                    let keeps_command = word.before.is_empty()
+                        && (COMMAND_PREFIXES.contains(&value.as_str()) || value.split_once('=').is_some_and(|(n, _)| is_valid_name(n)));
+                    if !keeps_command && !word.redirect {
+                        word.before.push(value);
+                    }
+                    word.redirect = false;
+                }
+                match c {
+                    '<' | '>' => word.redirect = true,
+                    ' ' | '\t' => {}
+                    _ => word.before.clear(),
+                }
+                quoted = false;
+                word.start = idx + 1;
+                word.tilde = chars.peek().is_some_and(|&(_, next)| next == '~');
+            }
+            _ => word.value.push(c),
+        }
+    }
+    if word.start == 0 {
+        word.tilde = text.starts_with('~');
+    }
+    word
+}
+
+/// A possible completion of the word.
+struct Candidate {
+    // the whole word, unquoted
+    value: String,
+    // how it is listed
+    display: String,
+    // a directory, so completing it does not end the word
+    dir: bool,
+}
+
+/// Builtins, aliases, functions and PATH commands starting with `prefix`.
+fn command_candidates(shell: &Shell, prefix: &str) -> Vec<Candidate> {
+    let mut names: Vec<String> = BUILTINS.iter().map(|b| b.name().to_string()).collect();
+    names.extend(shell.aliases.keys().cloned());
+    names.extend(shell.functions.keys().cloned());
+    let path = env::var("PATH").unwrap_or_default();
+    for dir in path.split(':').filter(|d| !d.is_empty()) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(prefix) && is_executable(&entry.path()) {
+                names.push(name);
+            }
+        }
+    }
+    names.retain(|n| n.starts_with(prefix));
+    names.sort();
+    names.dedup();
+    names.into_iter().map(|n| Candidate { value: n.clone(), display: n, dir: false }).collect()
+}
+
+/// Files whose path starts with `value`, of those that `keep` accepts.
+fn file_candidates(shell: &Shell, value: &str, tilde: bool, keep: fn(&Path) -> bool) -> Vec<Candidate> {
+    let (dir_part, prefix) = match value.rfind('/') {
+        Some(idx) => value.split_at(idx + 1),
+        None => ("", value),
+    };
+    // `~user` without a slash yet: finish it as a directory
+    if tilde && dir_part.is_empty() {
+        return match tilde_value(shell, &value[1..]) {
+            Some(_) => vec![Candidate { value: format!("{}/", value), display: format!("{}/", value), dir: true }],
+            None => Vec::new(),
+        };
+    }
+
+    let dir = if dir_part.is_empty() {
+        ".".to_string()
+    } else if tilde {
+        let (user, rest) = dir_part.split_once('/').unwrap_or((dir_part, ""));
+        match tilde_value(shell, &user[1..]) {
+            Some(home) => format!("{}/{}", home, rest),
+            None => return Vec::new(),
+        }
+    } else {
+        dir_part.to_string()
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // dotfiles only when asked for
+        if !name.starts_with(prefix) || name.starts_with('.') && !prefix.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if !keep(&path) {
+            continue;
+        }
+        let dir = path.is_dir();
+        let suffix = if dir { "/" } else { "" };
+        found.push(Candidate { value: format!("{}{}{}", dir_part, name, suffix), display: format!("{}{}", name, suffix), dir });
+    }
+    found.sort_by(|a, b| a.display.cmp(&b.display));
+    found
+}
+
+/// `-x` for every flag the builtin `command` knows that starts like `value`.
+fn flag_candidates(command: &str, value: &str) -> Vec<Candidate> {
+    let Some(builtin) = find_builtin(command) else {
+        return Vec::new();
+    };
+    builtin
+        .flags()
+        .chars()
+        .map(|c| format!("-{}", c))
+        .filter(|flag| flag.starts_with(value))
+        .map(|flag| Candidate { value: flag.clone(), display: flag, dir: false })
+        .collect()
+}
+
+/// Quote a completed word the way it was being typed: in the quotes that
+/// are open, or with backslashes. A leading `~user/` stays unquoted so it
+/// is still expanded.
+fn quote_word(value: &str, quote: Option<char>, tilde: bool) -> String {
+    let (head, rest) = match value.find('/') {
+        Some(idx) if tilde => value.split_at(idx),
+        _ => ("", value),
+    };
+    let mut out = head.to_string();
+    match quote {
+        Some('\'') => {
+            out.push('\'');
+            out.push_str(&rest.replace('\'', "'\\''"));
+        }
+        Some(q) => {
+            out.push(q);
+            for c in rest.chars() {
+                if matches!(c, '"' | '\\' | '$' | '`') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+        None => {
+            for (idx, c) in rest.chars().enumerate() {
+                let special = " \t\n'\"\\$`&;|<>()*?[]!{}".contains(c) || c == '#' && idx == 0 && head.is_empty();
+                if special {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// The longest text all candidates start with.
+fn common_prefix(candidates: &[Candidate]) -> String {
+    let mut common = candidates[0].value.clone();
+    for candidate in &candidates[1..] {
+        let len = common
+            .char_indices()
+            .zip(candidate.value.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((idx, c), _)| idx + c.len_utf8());
+        common.truncate(len);
+    }
+    common
+}
+
+/// Complete the word before char `cursor` in `text`: a command name in
+/// command position, a flag of a builtin after `-`, and a file otherwise.
+pub fn complete(shell: &Shell, text: &str, cursor: usize) -> Completion {
+    let before: String = text.chars().take(cursor).collect();
+    let word = current_word(&before);
+    let command_position = word.before.is_empty() && !word.redirect;
+    // which files fit: commands run programs, `cd` goes to directories
+    let keep: fn(&Path) -> bool = match word.before.first() {
+        _ if word.redirect => |_| true,
+        None => |path| path.is_dir() || is_executable(path),
+        Some(command) if command == "cd" => Path::is_dir,
+        Some(_) => |_| true,
+    };
+
+    let candidates = match word.before.first() {
+        Some(command) if word.value.starts_with('-') && word.quote.is_none() => flag_candidates(command, &word.value),
+        _ if command_position && !word.value.contains('/') && !word.tilde => command_candidates(shell, &word.value),
+        _ => file_candidates(shell, &word.value, word.tilde, keep),
+    };
+
+    let mut completion = Completion { start: word.start, ..Completion::default() };
+    match candidates.as_slice() {
+        [] => {}
+        [only] => {
+            let mut replacement = quote_word(&only.value, word.quote, word.tilde);
+            if !only.dir {
+                if let Some(q) = word.quote {
+                    replacement.push(q);
+                }
+                replacement.push(' ');
+            }
+            completion.replacement = Some(replacement);
+        }
+        _ => {
+            let common = common_prefix(&candidates);
+            if common.len() > word.value.len() {
+                completion.replacement = Some(quote_word(&common, word.quote, word.tilde));
+            } else {
+                completion.choices = candidates.into_iter().map(|c| c.display).collect();
+            }
+        }
+    }
+    completion
+}
@@ -0,0 +1,105 @@
+use crate::editor::terminal::*;
+use std::io;
+
+/// How long to wait for the rest of an escape sequence, in milliseconds.
+const ESCAPE_TIMEOUT: i32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    // Ctrl and a letter, given in lowercase
+    Ctrl(char),
+    // Alt (or Esc) and a character
+    Alt(char),
+    Enter,
+    Tab,
+    Backspace,
+    AltBackspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    WordLeft,
+    WordRight,
+    Esc,
+    // a sequence we do not know, ignored
+    Unknown,
+}
+
+/// Read the rest of a UTF-8 character whose first byte is `first`.
+fn read_utf8(first: u8) -> io::Result<Key> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Ok(Key::Unknown),
+    };
+    let mut bytes = vec![first];
+    while bytes.len() < len {
+        match read_byte(None)? {
+            Some(b) => bytes.push(b),
+            None => break,
+        }
+    }
+    Ok(match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+        Some(c) => Key::Char(c),
+        None => Key::Unknown,
+    })
+}
+
+/// Read a CSI (`ESC [`) or SS3 (`ESC O`) sequence after its introducer.
+fn read_sequence() -> io::Result<Key> {
+    let mut params = String::new();
+    let last = loop {
+        match read_byte(Some(ESCAPE_TIMEOUT))? {
+            Some(b @ (b'0'..=b'9' | b';')) => params.push(b as char),
+            Some(b) => break b,
+            None => return Ok(Key::Unknown),
+        }
+    };
+    // a modifier after `;`: 5 is Ctrl, 3 is Alt
+    let modified = params.ends_with(";5") || params.ends_with(";3");
+
+    Ok(match (last, params.split(';').next().unwrap_or("")) {
+        (b'A', _) => Key::Up,
+        (b'B', _) => Key::Down,
+        (b'C', _) if modified => Key::WordRight,
+        (b'D', _) if modified => Key::WordLeft,
+        (b'C', _) => Key::Right,
+        (b'D', _) => Key::Left,
+        (b'H', _) => Key::Home,
+        (b'F', _) => Key::End,
+        (b'~', "1" | "7") => Key::Home,
+        (b'~', "4" | "8") => Key::End,
+        (b'~', "3") => Key::Delete,
+        _ => Key::Unknown,
+    })
+}
+
+/// Read one key press. None means stdin was closed.
+pub fn read_key() -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(None)? else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => match read_byte(Some(ESCAPE_TIMEOUT))? {
+            None => Key::Esc,
+            Some(b'[' | b'O') => read_sequence()?,
+            Some(0x7f | 0x08) => Key::AltBackspace,
+            Some(b) if b.is_ascii() => Key::Alt(b as char),
+            Some(_) => Key::Unknown,
+        },
+        1..=26 => Key::Ctrl((b'a' + byte - 1) as char),
+        0x20..=0x7e => Key::Char(byte as char),
+        0x80.. => read_utf8(byte)?,
+        _ => Key::Unknown,
+    };
+    Ok(Some(key))
+}
@@ -0,0 +1,321 @@
+use crate::commands::ls::*;
+use crate::editor::buffer::*;
+use crate::editor::complete::*;
+use crate::editor::keys::*;
+use crate::editor::render::*;
+use crate::editor::search::*;
+use crate::editor::terminal::*;
+use std::io::{self, Write};
+
+/// Tells whether the text typed so far is a whole command. If it is not,
+/// returns the prompt for the line that continues it (`quote> ` and such).
+pub type Continuation<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Works out what Tab does, given the text and the cursor's char index.
+pub type Completer<'a> = &'a dyn Fn(&str, usize) -> Completion;
+
+/// Past this many choices, ask before listing them all.
+const LIST_QUERY_LIMIT: usize = 100;
+
+fn write_out(text: &str) -> io::Result<()> {
+    let mut out = io::stdout();
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// Reads command lines from the terminal with emacs-style editing.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    // the last killed text, for Ctrl-Y
+    kill: String,
+    // what the last search looked for, for Ctrl-R right after Ctrl-R
+    last_search: String,
+}
+
+/// What is being edited during one `read_line`.
+struct Session<'a> {
+    buffer: LineBuffer,
+    renderer: Renderer,
+    prompt: &'a str,
+    continuation: Continuation<'a>,
+    completer: Completer<'a>,
+    // the history with any edits made while browsing it, and the new line last
+    entries: Vec<String>,
+    index: usize,
+    // a Ctrl-R search going on, and the entry and text from before it
+    search: Option<Search>,
+    before_search: Option<(usize, LineBuffer)>,
+}
+
+impl Session<'_> {
+    /// The prompt for each line of the text: the main one, then the
+    /// continuation prompt the text before each later line asks for.
+    fn prompts(&self) -> Vec<String> {
+        let text = self.buffer.text();
+        let first = match &self.search {
+            Some(search) => search.prompt(),
+            None => self.prompt.to_string(),
+        };
+        let mut prompts = vec![first];
+        for (idx, c) in text.char_indices() {
+            if c == '\n' {
+                prompts.push((self.continuation)(&text[..idx]).unwrap_or_else(|| "> ".to_string()));
+            }
+        }
+        prompts
+    }
+
+    fn redraw(&mut self) -> io::Result<()> {
+        let prompts = self.prompts();
+        let highlight = self.search.as_ref().and_then(Search::highlight);
+        let out = self.renderer.draw(&prompts, &self.buffer, highlight, width());
+        write_out(&out)
+    }
+
+    /// Leave the edited text on screen with `suffix` after it, and move to
+    /// the next line unless input ended (the shell then prints `exit`).
+    fn finish(&mut self, suffix: &str, newline: bool) -> io::Result<()> {
+        let end = self.buffer.chars().len();
+        while self.buffer.cursor() < end {
+            self.buffer.move_right();
+        }
+        self.redraw()?;
+        let mut out = suffix.to_string();
+        if newline {
+            out.push_str(&self.renderer.finish());
+        }
+        write_out(&out)
+    }
+
+    /// Up and Down move between the lines of a multi-line text, and past
+    /// its first or last line to the previous or next history entry.
+    fn move_vertical(&mut self, up: bool) {
+        if up && !self.buffer.on_first_line() || !up && !self.buffer.on_last_line() {
+            self.buffer.move_line(up);
+            return;
+        }
+        let target = if up { self.index.checked_sub(1) } else { Some(self.index + 1) };
+        let Some(target) = target.filter(|&t| t < self.entries.len()) else {
+            return;
+        };
+        self.entries[self.index] = self.buffer.text();
+        self.index = target;
+        self.buffer.set(&self.entries[target]);
+    }
+
+    fn complete(&mut self) -> io::Result<()> {
+        let completion = (self.completer)(&self.buffer.text(), self.buffer.cursor());
+        match completion.replacement {
+            Some(text) => self.buffer.replace_before_cursor(completion.start, &text),
+            None if completion.choices.len() > 1 => self.list_choices(&completion.choices)?,
+            // nothing matches: ring the bell
+            None => write_out("\x07")?,
+        }
+        Ok(())
+    }
+
+    /// Show the choices below the text in columns, the way `ls` does. The
+    /// next redraw puts the prompt and text back under them.
+    fn list_choices(&mut self, choices: &[String]) -> io::Result<()> {
+        write_out(&self.renderer.finish())?;
+        if choices.len() > LIST_QUERY_LIMIT {
+            write_out(&format!("Display all {} possibilities? (y or n)", choices.len()))?;
+            let show = loop {
+                match read_key()? {
+                    Some(Key::Char('y' | 'Y' | ' ')) => break true,
+                    Some(Key::Char('n' | 'N') | Key::Ctrl('c' | 'g') | Key::Esc) | None => break false,
+                    _ => {}
+                }
+            };
+            write_out("\r\n")?;
+            if !show {
+                return Ok(());
+            }
+        }
+        let mut listing = String::new();
+        format_columns(choices, Some(width()), &mut listing);
+        write_out(&listing)
+    }
+
+    fn start_search(&mut self, forward: bool) {
+        self.entries[self.index] = self.buffer.text();
+        self.before_search = Some((self.index, self.buffer.clone()));
+        self.search = Some(Search::new(self.index, forward));
+    }
+
+    /// Show the entry the search found with the cursor on the match, or the
+    /// text from before the search when nothing matched yet.
+    fn show_match(&mut self) {
+        match self.search.as_ref().and_then(|s| s.found) {
+            Some((entry, pos)) => {
+                self.buffer.set(&self.entries[entry]);
+                self.buffer.set_cursor(pos);
+            }
+            None => {
+                if let Some((_, buffer)) = &self.before_search {
+                    self.buffer = buffer.clone();
+                }
+            }
+        }
+    }
+
+    /// Handle a key during a search. Keys that are not part of searching end
+    /// it with the found entry left to edit, and are returned to be handled
+    /// as usual (all but Esc).
+    fn search_key(&mut self, key: Key, last_query: &mut String) -> Option<Key> {
+        // the new line at the end is not part of the history
+        let history = &self.entries[..self.entries.len() - 1];
+        let search = self.search.as_mut()?;
+        match key {
+            Key::Ctrl('r') => search.step(false, history, last_query),
+            Key::Ctrl('s') => search.step(true, history, last_query),
+            Key::Char(c) => search.push(c, history),
+            Key::Backspace | Key::Ctrl('h') => search.pop(),
+            // give up and go back to the text from before the search
+            Key::Ctrl('g') => {
+                if let Some((index, buffer)) = self.before_search.take() {
+                    self.index = index;
+                    self.buffer = buffer;
+                }
+                self.search = None;
+                return None;
+            }
+            _ => {
+                if let Some((entry, _)) = search.found {
+                    self.index = entry;
+                }
+                if !search.query.is_empty() {
+                    *last_query = search.query.clone();
+                }
+                self.search = None;
+                self.before_search = None;
+                return (key != Key::Esc).then_some(key);
+            }
+        }
+        self.show_match();
+        None
+    }
+}
+
+impl LineEditor {
+    /// Read a command from the terminal, showing `prompt` first. Enter on an
+    /// unfinished command starts a new line of it instead of returning.
+    /// Returns None when Ctrl-D is pressed on an empty line, and an
+    /// `Interrupted` error for Ctrl-C.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        history: Vec<String>,
+        continuation: Continuation,
+        completer: Completer,
+    ) -> io::Result<Option<String>> {
+        let _raw = RawMode::enable()?;
+        let mut entries = history;
+        entries.push(String::new());
+        let mut session = Session {
+            buffer: LineBuffer::default(),
+            renderer: Renderer::default(),
+            prompt,
+            continuation,
+            completer,
+            index: entries.len() - 1,
+            entries,
+            search: None,
+            before_search: None,
+        };
+        session.redraw()?;
+
+        // kills right after each other add up to one piece of text for Ctrl-Y
+        let mut last_was_kill = false;
+        loop {
+            let Some(mut key) = read_key()? else {
+                session.finish("", false)?;
+                return Ok(None);
+            };
+            if session.search.is_some() {
+                match session.search_key(key, &mut self.last_search) {
+                    Some(rest) => key = rest,
+                    None => {
+                        session.redraw()?;
+                        continue;
+                    }
+                }
+            }
+            let buffer = &mut session.buffer;
+            let mut killed = None;
+            let mut killed_back = false;
+
+            match key {
+                Key::Enter => {
+                    let text = buffer.text();
+                    if (session.continuation)(&text).is_some() {
+                        buffer.set(&text);
+                        buffer.insert('\n');
+                    } else {
+                        session.finish("", true)?;
+                        return Ok(Some(text));
+                    }
+                }
+                Key::Ctrl('c') => {
+                    session.finish("^C", true)?;
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                Key::Ctrl('d') if buffer.is_empty() => {
+                    session.finish("", false)?;
+                    return Ok(None);
+                }
+                Key::Ctrl('d') | Key::Delete => buffer.delete_forward(),
+                Key::Backspace | Key::Ctrl('h') => buffer.delete_back(),
+
+                Key::Char(c) => buffer.insert(c),
+                Key::Left | Key::Ctrl('b') => buffer.move_left(),
+                Key::Right | Key::Ctrl('f') => buffer.move_right(),
+                Key::Home | Key::Ctrl('a') => buffer.move_home(),
+                Key::End | Key::Ctrl('e') => buffer.move_end(),
+                Key::WordLeft | Key::Alt('b') => buffer.word_left(),
+                Key::WordRight | Key::Alt('f') => buffer.word_right(),
+                Key::Up | Key::Ctrl('p') => session.move_vertical(true),
+                Key::Down | Key::Ctrl('n') => session.move_vertical(false),
+                Key::Tab => session.complete()?,
+                Key::Ctrl('r') => session.start_search(false),
+                Key::Ctrl('s') => session.start_search(true),
+
+                Key::Ctrl('k') => killed = Some(buffer.kill_to_end()),
+                Key::Alt('d') => killed = Some(buffer.kill_word_forward()),
+                Key::Ctrl('u') => {
+                    killed = Some(buffer.kill_to_start());
+                    killed_back = true;
+                }
+                Key::Ctrl('w') => {
+                    killed = Some(buffer.kill_big_word_back());
+                    killed_back = true;
+                }
+                Key::AltBackspace => {
+                    killed = Some(buffer.kill_word_back());
+                    killed_back = true;
+                }
+                Key::Ctrl('y') => buffer.insert_str(&self.kill),
+                Key::Ctrl('t') => buffer.transpose(),
+
+                Key::Ctrl('l') => {
+                    write_out("\x1b[H\x1b[2J")?;
+                    session.renderer.reset();
+                }
+                _ => {}
+            }
+
+            if let Some(text) = &killed {
+                if !last_was_kill {
+                    self.kill.clear();
+                }
+                if killed_back {
+                    self.kill.insert_str(0, text);
+                } else {
+                    self.kill.push_str(text);
+                }
+            }
+            last_was_kill = killed.is_some();
+            session.redraw()?;
+        }
+    }
+}
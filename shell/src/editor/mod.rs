@@ -0,0 +1,7 @@
+pub mod buffer;
+pub mod complete;
+pub mod keys;
+pub mod line_editor;
+pub mod render;
+pub mod search;
+pub mod terminal;
\ No newline at end of file
@@ -0,0 +1,156 @@
+use crate::editor::buffer::*;
+use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// A spot on the screen, counted from the first row the editor drew on.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Position {
+    row: usize,
+    col: usize,
+}
+
+impl Position {
+    /// Move past a character drawn at this position, wrapping at `width`
+    /// like the terminal does.
+    fn advance(&mut self, c: char, width: usize) {
+        if c == '\n' {
+            self.row += 1;
+            self.col = 0;
+            return;
+        }
+        let w = c.width().unwrap_or(0);
+        if self.col + w > width {
+            self.row += 1;
+            self.col = 0;
+        }
+        self.col += w;
+    }
+
+    /// A cursor right after the last column shows at the start of the next row.
+    fn wrapped(self, width: usize) -> Position {
+        if self.col >= width { Position { row: self.row + 1, col: 0 } } else { self }
+    }
+}
+
+/// Copy a prompt to `out`, moving `pos` over what it shows. Escape
+/// sequences (colors and the like) take no room on screen.
+fn draw_prompt(out: &mut String, prompt: &str, pos: &mut Position, width: usize) {
+    let mut chars = prompt.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c != '\x1b' {
+            pos.advance(c, width);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters up to a final byte in @..~
+            Some('[') => {
+                out.push('[');
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC (window title): up to BEL or ESC \
+            Some(']') => {
+                out.push(']');
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        out.push(chars.next().unwrap_or_default());
+                        break;
+                    }
+                }
+            }
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+}
+
+/// Draws the prompt and the text being edited, and remembers where it left
+/// the cursor so the next draw can start over from the same place.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    // rows between the first row drawn and the cursor
+    cursor_row: usize,
+    // rows between the first row drawn and the end of the text
+    end_row: usize,
+}
+
+impl Renderer {
+    /// Redraw everything. `prompts[i]` is shown before line `i` of the text,
+    /// and the chars in `highlight` (a search match) are shown reversed.
+    pub fn draw(&mut self, prompts: &[String], buffer: &LineBuffer, highlight: Option<(usize, usize)>, width: usize) -> String {
+        let mut out = String::new();
+        if self.cursor_row > 0 {
+            let _ = write!(out, "\x1b[{}A", self.cursor_row);
+        }
+        out.push_str("\r\x1b[J");
+
+        let mut pos = Position::default();
+        let mut cursor = None;
+        let mut line = 0;
+        draw_prompt(&mut out, prompts.first().map_or("", |p| p.as_str()), &mut pos, width);
+        for (idx, &c) in buffer.chars().iter().enumerate() {
+            if idx == buffer.cursor() {
+                cursor = Some(pos.wrapped(width));
+            }
+            if highlight.is_some_and(|(start, _)| start == idx) {
+                out.push_str("\x1b[7m");
+            }
+            out.push(c);
+            if highlight.is_some_and(|(_, end)| end == idx + 1) {
+                out.push_str("\x1b[27m");
+            }
+            pos.advance(c, width);
+            if c == '\n' {
+                line += 1;
+                draw_prompt(&mut out, prompts.get(line).map_or("", |p| p.as_str()), &mut pos, width);
+            }
+        }
+
+        // at the right edge the terminal waits for the next character to
+        // wrap; go to the next row now so cursor movement stays predictable
+        if pos.col >= width {
+            out.push('\n');
+        }
+        let end = pos.wrapped(width);
+        let cursor = cursor.unwrap_or(end);
+
+        if end.row > cursor.row {
+            let _ = write!(out, "\x1b[{}A", end.row - cursor.row);
+        }
+        out.push('\r');
+        if cursor.col > 0 {
+            let _ = write!(out, "\x1b[{}C", cursor.col);
+        }
+
+        self.cursor_row = cursor.row;
+        self.end_row = end.row;
+        out
+    }
+
+    /// Move below the text so the command's output starts on a fresh line.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        if self.end_row > self.cursor_row {
+            let _ = write!(out, "\x1b[{}B", self.end_row - self.cursor_row);
+        }
+        out.push_str("\r\n");
+        self.cursor_row = 0;
+        self.end_row = 0;
+        out
+    }
+
+    /// Forget the old drawing, after the screen was cleared.
+    pub fn reset(&mut self) {
+        self.cursor_row = 0;
+        self.end_row = 0;
+    }
+}
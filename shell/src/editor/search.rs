@@ -0,0 +1,128 @@
+/// Where `query` occurs in `line`, as a char index: the last occurrence
+/// when searching backward, the first going forward.
+fn find_in(line: &str, query: &str, forward: bool) -> Option<usize> {
+    let byte = if forward { line.find(query) } else { line.rfind(query) }?;
+    Some(line[..byte].chars().count())
+}
+
+/// The nearest entry from `from` on (itself included) that contains
+/// `query`, and where the match starts in it.
+fn find_entry(entries: &[String], query: &str, from: usize, forward: bool) -> Option<(usize, usize)> {
+    if forward {
+        (from..entries.len()).find_map(|i| find_in(&entries[i], query, true).map(|pos| (i, pos)))
+    } else {
+        (0..=from.min(entries.len().checked_sub(1)?))
+            .rev()
+            .find_map(|i| find_in(&entries[i], query, false).map(|pos| (i, pos)))
+    }
+}
+
+/// A point in the search that Backspace can go back to.
+#[derive(Debug, Clone)]
+struct Step {
+    query: String,
+    found: Option<(usize, usize)>,
+    failed: bool,
+}
+
+/// Ctrl-R / Ctrl-S: find history entries as the text to look for is typed.
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub forward: bool,
+    pub query: String,
+    // the matching entry shown and where the match starts in it
+    pub found: Option<(usize, usize)>,
+    // the last change to the query or step matched nothing
+    pub failed: bool,
+    // the entry that was being edited when the search began
+    start: usize,
+    undo: Vec<Step>,
+}
+
+impl Search {
+    pub fn new(start: usize, forward: bool) -> Self {
+        Search { forward, query: String::new(), found: None, failed: false, start, undo: Vec::new() }
+    }
+
+    /// Shown instead of the prompt while searching, as bash does.
+    pub fn prompt(&self) -> String {
+        format!(
+            "({}{}i-search)`{}': ",
+            if self.failed { "failed " } else { "" },
+            if self.forward { "" } else { "reverse-" },
+            self.query
+        )
+    }
+
+    /// Where the match in the shown entry starts and ends, in chars.
+    pub fn highlight(&self) -> Option<(usize, usize)> {
+        let (_, pos) = self.found?;
+        Some((pos, pos + self.query.chars().count()))
+    }
+
+    /// The first entry a new search looks at: the one next to where the
+    /// search began.
+    fn first(&self, forward: bool) -> Option<usize> {
+        if forward { Some(self.start + 1) } else { self.start.checked_sub(1) }
+    }
+
+    /// Add a character to the query. The entry shown stays if it still
+    /// matches; otherwise the search goes on in the same direction.
+    pub fn push(&mut self, c: char, entries: &[String]) {
+        self.undo.push(Step { query: self.query.clone(), found: self.found, failed: self.failed });
+        self.query.push(c);
+        let from = match self.found {
+            Some((entry, _)) => Some(entry),
+            None => self.first(self.forward),
+        };
+        let found = from.and_then(|from| find_entry(entries, &self.query, from, self.forward));
+        self.failed = found.is_none();
+        if found.is_some() {
+            self.found = found;
+        }
+    }
+
+    /// Backspace: undo the last character typed.
+    pub fn pop(&mut self) {
+        if let Some(step) = self.undo.pop() {
+            self.query = step.query;
+            self.found = step.found;
+            self.failed = step.failed;
+        }
+    }
+
+    /// Ctrl-R (backward) or Ctrl-S (forward) again: the next entry that
+    /// matches, skipping ones with the same text. With nothing typed yet it
+    /// searches for `last_query` again.
+    pub fn step(&mut self, forward: bool, entries: &[String], last_query: &str) {
+        self.forward = forward;
+        if self.query.is_empty() {
+            if last_query.is_empty() {
+                return;
+            }
+            for c in last_query.chars() {
+                self.push(c, entries);
+            }
+            return;
+        }
+
+        let current = self.found.map(|(entry, _)| entries[entry].as_str());
+        let mut from = match self.found {
+            Some((entry, _)) if forward => Some(entry + 1),
+            Some((entry, _)) => entry.checked_sub(1),
+            None => self.first(forward),
+        };
+        while let Some(start) = from {
+            let Some((entry, pos)) = find_entry(entries, &self.query, start, forward) else {
+                break;
+            };
+            if Some(entries[entry].as_str()) != current {
+                self.found = Some((entry, pos));
+                self.failed = false;
+                return;
+            }
+            from = if forward { Some(entry + 1) } else { entry.checked_sub(1) };
+        }
+        self.failed = true;
+    }
+}
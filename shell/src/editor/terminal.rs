@@ -0,0 +1,79 @@
+use std::io;
+use std::mem::MaybeUninit;
+use terminal_size::{Width, terminal_size};
+
+const FALLBACK_WIDTH: usize = 80;
+
+/// The terminal switched to raw mode: keys arrive one at a time, unechoed,
+/// and Ctrl-C is a key instead of a signal. Dropping it restores the
+/// previous settings.
+pub struct RawMode {
+    saved: libc::termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        let mut term = MaybeUninit::<libc::termios>::uninit();
+        if unsafe { libc::tcgetattr(0, term.as_mut_ptr()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: tcgetattr filled it in
+        let saved = unsafe { term.assume_init() };
+
+        let mut raw = saved;
+        raw.c_iflag &= !(libc::ICRNL | libc::IXON | libc::BRKINT | libc::ISTRIP | libc::INPCK);
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(0, libc::TCSADRAIN, &raw) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(0, libc::TCSADRAIN, &self.saved) };
+    }
+}
+
+/// Whether both stdin and stdout are terminals, so a line editor can be used.
+pub fn is_terminal() -> bool {
+    unsafe { libc::isatty(0) == 1 && libc::isatty(1) == 1 }
+}
+
+/// Width of the terminal in columns.
+pub fn width() -> usize {
+    terminal_size().map(|(Width(w), _)| w as usize).filter(|&w| w > 0).unwrap_or(FALLBACK_WIDTH)
+}
+
+/// Read one byte from stdin. With a timeout (in milliseconds), None means
+/// nothing arrived in time; without one, None means end of input.
+pub fn read_byte(timeout: Option<i32>) -> io::Result<Option<u8>> {
+    if let Some(ms) = timeout {
+        let mut fds = libc::pollfd { fd: 0, events: libc::POLLIN, revents: 0 };
+        match unsafe { libc::poll(&mut fds, 1, ms) } {
+            // a signal for a trap: take it as the wait running out
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => return Ok(None),
+            -1 => return Err(io::Error::last_os_error()),
+            0 => return Ok(None),
+            _ => {}
+        }
+    }
+
+    let mut byte = 0u8;
+    loop {
+        let n = unsafe { libc::read(0, (&mut byte as *mut u8).cast(), 1) };
+        match n {
+            1 => return Ok(Some(byte)),
+            0 => return Ok(None),
+            _ => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::exec::dispatch::*;
+use crate::exec::job::*;
+use crate::exec::list::*;
+use crate::exec::redirect::*;
+use crate::exec::signal::*;
+use crate::parsing::ast::*;
+use crate::parsing::expand::*;
+use crate::parsing::glob::*;
+use crate::variables::shell::*;
+use std::io::{self, Write};
+
+/// Run a single command in the shell process and return its exit status.
+pub fn execute(shell: &mut Shell, command: &Command) -> i32 {
+    match command {
+        Command::Simple(cmd) => run_simple(shell, cmd),
+        Command::FunctionDef { name, body, .. } => {
+            shell.functions.insert(name.clone(), body.clone());
+            0
+        }
+        Command::Compound { body, redirects, .. } => {
+            let saved = match expand_redirects(shell, redirects).and_then(|r| apply_redirects(&r)) {
+                Ok(saved) => saved,
+                Err(e) => {
+                    eprintln!("0-shell: {}", e);
+                    return 1;
+                }
+            };
+            let status = match body {
+                CompoundCommand::Group(list) => run_list(shell, list),
+                CompoundCommand::Subshell(list) => run_subshell(shell, list),
+                CompoundCommand::If { branches, else_body } => run_if(shell, branches, else_body.as_ref()),
+                CompoundCommand::While { cond, body, until } => run_while(shell, cond, body, *until),
+                CompoundCommand::For { name, words, body } => run_for(shell, name, words.as_deref(), body),
+                CompoundCommand::Case { word, arms } => run_case(shell, word, arms),
+            };
+            saved.restore();
+            status
+        }
+    }
+}
+
+fn run_simple(shell: &mut Shell, cmd: &SimpleCommand) -> i32 {
+    shell.subst_status = None;
+    let expanded = match expand_command(shell, cmd) {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            eprintln!("0-shell: {}", e);
+            return 1;
+        }
+    };
+    let saved = match apply_redirects(&expanded.redirects) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("0-shell: {}", e);
+            return 1;
+        }
+    };
+
+    let status = if expanded.argv.is_empty() {
+        // a command of only assignments sets shell variables; its status is
+        // the one of the last command substitution, if any
+        for (name, value) in &expanded.assignments {
+            shell.set_var(name, value);
+        }
+        shell.subst_status.unwrap_or(0)
+    } else {
+        let saved_vars = shell.push_temp_vars(&expanded.assignments);
+        let status = run_command(shell, &expanded.argv);
+        shell.restore_vars(saved_vars);
+        status
+    };
+    saved.restore();
+    status
+}
+
+/// Call a function: `argv[1..]` become the positional parameters and
+/// `local` variables get their old values back when it returns.
+pub fn call_function(shell: &mut Shell, body: &Command, argv: &[String]) -> i32 {
+    let saved_params = std::mem::replace(&mut shell.positional, argv[1..].to_vec());
+    // `break` in a function does not reach the loops of its caller
+    let saved_depth = std::mem::take(&mut shell.loop_depth);
+    shell.locals.push(Vec::new());
+
+    let status = execute(shell, body);
+    if shell.flow == Flow::Return {
+        shell.flow = Flow::Normal;
+    }
+
+    if let Some(frame) = shell.locals.pop() {
+        shell.restore_vars(frame);
+    }
+    shell.loop_depth = saved_depth;
+    shell.positional = saved_params;
+    status
+}
+
+/// Run a list in a forked copy of the shell so that nothing it changes, like
+/// variables or the current directory, leaks back.
+fn run_subshell(shell: &mut Shell, list: &List) -> i32 {
+    // anything still buffered would otherwise be printed by the child too
+    let _ = io::stdout().flush();
+
+    let pid = unsafe { libc::fork() };
+    if pid == -1 {
+        eprintln!("0-shell: fork: {}", io::Error::last_os_error());
+        return 1;
+    }
+    if pid == 0 {
+        enter_child(shell, Some((0, true)));
+        let status = run_list(shell, list);
+        let _ = io::stdout().flush();
+        unsafe { libc::_exit(status) };
+    }
+    let own_group = shell.jobs.control;
+    if own_group {
+        unsafe { libc::setpgid(pid, pid) };
+        shell.jobs.give_terminal(pid);
+    }
+    let text = shell.jobs.foreground.clone();
+    wait_foreground(shell, Job::new(pid, &[pid], &text, own_group))
+}
+
+/// Run the condition of an `if` or a loop.
+fn run_condition(shell: &mut Shell, cond: &List) -> i32 {
+    shell.condition_depth += 1;
+    let status = run_list(shell, cond);
+    shell.condition_depth -= 1;
+    status
+}
+
+fn run_if(shell: &mut Shell, branches: &[(List, List)], else_body: Option<&List>) -> i32 {
+    for (cond, body) in branches {
+        if run_condition(shell, cond) == 0 && shell.flow == Flow::Normal {
+            return run_list(shell, body);
+        }
+        if shell.flow != Flow::Normal {
+            return shell.last_status;
+        }
+    }
+    match else_body {
+        Some(body) => run_list(shell, body),
+        None => 0,
+    }
+}
+
+/// Called after a loop body ran: whether the loop should stop because of a
+/// `break` or a `continue` meant for an outer loop, or Ctrl-C.
+fn leave_loop(shell: &mut Shell) -> bool {
+    if interrupted() {
+        return true;
+    }
+    match shell.flow {
+        Flow::Normal => false,
+        Flow::Break(n) => {
+            shell.flow = if n > 1 { Flow::Break(n - 1) } else { Flow::Normal };
+            true
+        }
+        Flow::Continue(n) => {
+            shell.flow = if n > 1 { Flow::Continue(n - 1) } else { Flow::Normal };
+            n > 1
+        }
+        Flow::Return => true,
+    }
+}
+
+fn run_while(shell: &mut Shell, cond: &List, body: &List, until: bool) -> i32 {
+    let mut status = 0;
+    shell.loop_depth += 1;
+    loop {
+        let passed = run_condition(shell, cond) == 0;
+        if leave_loop(shell) || passed == until {
+            break;
+        }
+        status = run_list(shell, body);
+        if leave_loop(shell) {
+            break;
+        }
+    }
+    shell.loop_depth -= 1;
+    status
+}
+
+fn run_for(shell: &mut Shell, name: &str, words: Option<&[Word]>, body: &List) -> i32 {
+    let values = match words {
+        Some(words) => {
+            let mut values = Vec::new();
+            for word in words {
+                match expand_word(shell, &word.text) {
+                    Ok(fields) => values.extend(fields),
+                    Err(e) => {
+                        eprintln!("0-shell: {}", e);
+                        return 1;
+                    }
+                }
+            }
+            values
+        }
+        None => shell.positional.clone(),
+    };
+
+    let mut status = 0;
+    shell.loop_depth += 1;
+    for value in values {
+        shell.set_var(name, &value);
+        status = run_list(shell, body);
+        if leave_loop(shell) {
+            break;
+        }
+    }
+    shell.loop_depth -= 1;
+    status
+}
+
+/// Run the body of the first arm with a pattern matching the word.
+fn run_case(shell: &mut Shell, word: &Word, arms: &[CaseArm]) -> i32 {
+    let value = match expand_string(shell, &word.text) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("0-shell: {}", e);
+            return 1;
+        }
+    };
+    for arm in arms {
+        for pattern in &arm.patterns {
+            match expand_pattern(shell, &pattern.text) {
+                Ok(pattern) if fnmatch(&pattern, &value) => return run_list(shell, &arm.body),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("0-shell: {}", e);
+                    return 1;
+                }
+            }
+        }
+    }
+    0
+}
@@ -0,0 +1,60 @@
+use crate::commands::builtin::*;
+use crate::commands::flags::*;
+use crate::exec::command::*;
+use crate::exec::external::*;
+use crate::variables::shell::*;
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+pub fn is_builtin(name: &str) -> bool {
+    find_builtin(name).is_some()
+}
+
+/// Run a single command (function, builtin or external) and return its exit
+/// status. `argv[0]` is the command name.
+pub fn run_command(shell: &mut Shell, argv: &[String]) -> i32 {
+    let Some((command, args)) = argv.split_first() else {
+        return 0;
+    };
+    // functions come before builtins and PATH
+    if let Some(body) = shell.functions.get(command).cloned() {
+        return call_function(shell, &body, argv);
+    }
+    match find_builtin(command) {
+        Some(builtin) => run_builtin(shell, builtin, argv),
+        None => run_external(shell, command, args),
+    }
+}
+
+/// Build the context for a builtin and run it. Flags it does not know are
+/// caught here for the builtins that `check_flags`.
+fn run_builtin(shell: &mut Shell, builtin: &dyn Builtin, argv: &[String]) -> i32 {
+    let args = &argv[1..];
+    let (flags, operands) = if builtin.check_flags() {
+        split_flags(args)
+    } else {
+        (Vec::new(), args.to_vec())
+    };
+    let mut stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut stderr = io::stderr();
+    let cwd = env::current_dir().ok().or_else(|| shell.get_var("PWD").map(PathBuf::from));
+    let mut ctx = Context {
+        argv,
+        args,
+        flags,
+        operands,
+        stdin: &mut stdin,
+        stdout: &mut stdout,
+        stderr: &mut stderr,
+        shell,
+        cwd,
+    };
+    if let Some(flag) = ctx.flags.iter().find(|f| !builtin.flags().contains(f.as_str())) {
+        let _ = writeln!(ctx.stderr, "{}: -{}: invalid option", builtin.name(), flag);
+        let _ = writeln!(ctx.stderr, "{}: usage: {}", builtin.name(), builtin.usage());
+        return 2;
+    }
+    builtin.run(&mut ctx)
+}
@@ -0,0 +1,94 @@
+use crate::exec::job::*;
+use crate::variables::shell::*;
+use std::env;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// Exit status used when the command could not be found.
+pub const STATUS_NOT_FOUND: i32 = 127;
+/// Exit status used when the command was found but could not be executed.
+pub const STATUS_NOT_EXECUTABLE: i32 = 126;
+
+/// Resolve a command name like execvp does: a name containing '/' is used
+/// as-is, anything else is looked up in every directory listed in PATH.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return if path.exists() { Some(path) } else { None };
+    }
+
+    let path_var = env::var("PATH").unwrap_or_else(|_| "/usr/local/bin:/usr/bin:/bin".to_string());
+    for dir in path_var.split(':') {
+        // an empty PATH entry means the current directory
+        let dir = if dir.is_empty() { "." } else { dir };
+        let candidate = Path::new(dir).join(name);
+        if is_executable(&candidate) {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    match path.metadata() {
+        Ok(meta) => meta.is_file() && meta.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+/// Convert a child's wait status to a shell exit status
+/// (128 + signal number when the child was killed by a signal).
+pub fn status_code(status: ExitStatus) -> i32 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code,
+        (None, Some(sig)) => 128 + sig,
+        _ => 1,
+    }
+}
+
+/// Run an external program found on PATH, wait for it and return its exit
+/// status. With job control it is a foreground job of its own.
+pub fn run_external(shell: &mut Shell, command: &str, args: &[String]) -> i32 {
+    let path = match find_in_path(command) {
+        Some(p) => p,
+        None => {
+            eprintln!("command not found: {}", command);
+            return STATUS_NOT_FOUND;
+        }
+    };
+
+    let mut process = Command::new(&path);
+    process.arg0(command).args(args);
+    let own_group = shell.jobs.control;
+    if own_group {
+        let tty = shell.jobs.tty();
+        // SAFETY: between fork and exec this only makes system calls
+        unsafe {
+            process.pre_exec(move || {
+                enter_group(tty, 0, true);
+                Ok(())
+            })
+        };
+    }
+    let child = match process.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}: {}", command, e);
+            return match e.kind() {
+                io::ErrorKind::NotFound => STATUS_NOT_FOUND,
+                _ => STATUS_NOT_EXECUTABLE,
+            };
+        }
+    };
+
+    let pid = child.id() as i32;
+    if own_group {
+        unsafe { libc::setpgid(pid, pid) };
+        shell.jobs.give_terminal(pid);
+    }
+    let text = shell.jobs.foreground.clone();
+    wait_foreground(shell, Job::new(pid, &[pid], &text, own_group))
+}
@@ -0,0 +1,492 @@
+use crate::exec::external::*;
+use crate::exec::signal::*;
+use crate::exec::trap::*;
+use crate::variables::shell::*;
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+/// Signal numbers, names without `SIG` and how `jobs` describes a job they
+/// killed.
+pub const SIGNALS: &[(i32, &str, &str)] = &[
+    (libc::SIGHUP, "HUP", "Hangup"),
+    (libc::SIGINT, "INT", "Interrupt"),
+    (libc::SIGQUIT, "QUIT", "Quit"),
+    (libc::SIGILL, "ILL", "Illegal instruction"),
+    (libc::SIGTRAP, "TRAP", "Trace/breakpoint trap"),
+    (libc::SIGABRT, "ABRT", "Aborted"),
+    (libc::SIGBUS, "BUS", "Bus error"),
+    (libc::SIGFPE, "FPE", "Floating point exception"),
+    (libc::SIGKILL, "KILL", "Killed"),
+    (libc::SIGUSR1, "USR1", "User defined signal 1"),
+    (libc::SIGSEGV, "SEGV", "Segmentation fault"),
+    (libc::SIGUSR2, "USR2", "User defined signal 2"),
+    (libc::SIGPIPE, "PIPE", "Broken pipe"),
+    (libc::SIGALRM, "ALRM", "Alarm clock"),
+    (libc::SIGTERM, "TERM", "Terminated"),
+    (libc::SIGCHLD, "CHLD", "Child exited"),
+    (libc::SIGCONT, "CONT", "Continued"),
+    (libc::SIGSTOP, "STOP", "Stopped (signal)"),
+    (libc::SIGTSTP, "TSTP", "Stopped"),
+    (libc::SIGTTIN, "TTIN", "Stopped (tty input)"),
+    (libc::SIGTTOU, "TTOU", "Stopped (tty output)"),
+    (libc::SIGURG, "URG", "Urgent I/O condition"),
+    (libc::SIGXCPU, "XCPU", "CPU time limit exceeded"),
+    (libc::SIGXFSZ, "XFSZ", "File size limit exceeded"),
+    (libc::SIGVTALRM, "VTALRM", "Virtual timer expired"),
+    (libc::SIGPROF, "PROF", "Profiling timer expired"),
+    (libc::SIGWINCH, "WINCH", "Window changed"),
+    (libc::SIGIO, "IO", "I/O possible"),
+    (libc::SIGPWR, "PWR", "Power failure"),
+    (libc::SIGSYS, "SYS", "Bad system call"),
+];
+
+/// The number of a signal given as `TERM`, `SIGTERM` or `15`.
+pub fn signal_number(name: &str) -> Option<i32> {
+    if let Ok(n) = name.parse() {
+        return (0..=64).contains(&n).then_some(n);
+    }
+    let name = name.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    SIGNALS.iter().find(|(_, n, _)| *n == name).map(|(num, _, _)| *num)
+}
+
+/// What became of one process of a job.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProcessState {
+    Running,
+    // by the signal with this number
+    Stopped(i32),
+    // with this shell exit status (128 + signal when killed)
+    Exited(i32),
+}
+
+/// Where a job is at, worked out from its processes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobState {
+    Running,
+    // by the signal with this number
+    Stopped(i32),
+    // with the status of its last process
+    Done(i32),
+}
+
+/// A pipeline, or an and-or list started with `&`, running as one process
+/// group.
+#[derive(Clone)]
+pub struct Job {
+    pub id: usize,
+    // the process group, which is the first process
+    pub pgid: i32,
+    processes: Vec<(i32, ProcessState)>,
+    // the command line, as typed
+    pub text: String,
+    // the processes have a group of their own, so signals go to all of it
+    own_group: bool,
+    // there is news to report at the next prompt
+    changed: bool,
+    // terminal settings it had when it was stopped, back when it goes on
+    tmodes: Option<libc::termios>,
+    // started by the shell this one was forked from, the only one that can
+    // wait for it
+    inherited: bool,
+}
+
+impl Job {
+    pub fn new(pgid: i32, pids: &[i32], text: &str, own_group: bool) -> Self {
+        Job {
+            id: 0,
+            pgid,
+            processes: pids.iter().map(|&pid| (pid, ProcessState::Running)).collect(),
+            text: text.to_string(),
+            own_group,
+            changed: false,
+            tmodes: None,
+            inherited: false,
+        }
+    }
+
+    pub fn pids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.processes.iter().map(|(pid, _)| *pid)
+    }
+
+    pub fn state(&self) -> JobState {
+        let running = self.processes.iter().any(|(_, s)| *s == ProcessState::Running);
+        let stopped = self.processes.iter().find_map(|(_, s)| match s {
+            ProcessState::Stopped(sig) => Some(*sig),
+            _ => None,
+        });
+        match (running, stopped) {
+            (false, Some(sig)) => JobState::Stopped(sig),
+            (false, None) => match self.processes.last() {
+                Some((_, ProcessState::Exited(status))) => JobState::Done(*status),
+                _ => JobState::Done(0),
+            },
+            _ => JobState::Running,
+        }
+    }
+
+    /// Note what `waitpid` said about one of the processes.
+    fn record(&mut self, pid: i32, raw: i32) {
+        let Some((_, state)) = self.processes.iter_mut().find(|(p, _)| *p == pid) else {
+            return;
+        };
+        *state = if libc::WIFSTOPPED(raw) {
+            ProcessState::Stopped(libc::WSTOPSIG(raw))
+        } else if libc::WIFCONTINUED(raw) {
+            ProcessState::Running
+        } else {
+            ProcessState::Exited(status_code(ExitStatus::from_raw(raw)))
+        };
+        self.changed = true;
+    }
+
+    /// Look at the processes that have not exited, or only the running
+    /// ones unless `options` has `WCONTINUED`. Blocks until each has changed
+    /// state unless it has `WNOHANG`, or until Ctrl-C, which returns false.
+    fn poll(&mut self, options: i32) -> bool {
+        for idx in 0..self.processes.len() {
+            let (pid, state) = self.processes[idx];
+            let stopped = matches!(state, ProcessState::Stopped(_));
+            if matches!(state, ProcessState::Exited(_)) || stopped && options & libc::WCONTINUED == 0 {
+                continue;
+            }
+            let mut raw = 0;
+            let found = unsafe { libc::waitpid(pid, &mut raw, options) };
+            if found == -1 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                return !interrupted();
+            }
+            match found {
+                0 => {}
+                // someone else reaped it; nothing more will be heard of it
+                -1 => self.processes[idx].1 = ProcessState::Exited(STATUS_NOT_FOUND),
+                _ => self.record(pid, raw),
+            }
+        }
+        true
+    }
+
+    /// Wait until no process of the job is running any more, or Ctrl-C.
+    pub fn wait(&mut self) {
+        while self.state() == JobState::Running && self.poll(libc::WUNTRACED) {}
+    }
+
+    /// Send a signal to every process of the job.
+    pub fn signal(&self, sig: i32) -> io::Result<()> {
+        let sent = if self.own_group {
+            unsafe { libc::kill(-self.pgid, sig) }
+        } else {
+            self.pids().map(|pid| unsafe { libc::kill(pid, sig) }).max().unwrap_or(0)
+        };
+        if sent == -1 { Err(io::Error::last_os_error()) } else { Ok(()) }
+    }
+
+    /// Send `SIGCONT` and count its stopped processes as running again.
+    pub fn resume(&mut self) -> io::Result<()> {
+        self.signal(libc::SIGCONT)?;
+        for (_, state) in &mut self.processes {
+            if matches!(state, ProcessState::Stopped(_)) {
+                *state = ProcessState::Running;
+            }
+        }
+        Ok(())
+    }
+
+    /// The state as `jobs` shows it: `Running`, `Stopped`, `Done`,
+    /// `Exit 2`, `Killed`...
+    pub fn describe_state(&self) -> String {
+        let describe = |sig: i32| SIGNALS.iter().find(|(n, _, _)| *n == sig).map_or(format!("Signal {}", sig), |s| s.2.to_string());
+        match self.state() {
+            JobState::Running => "Running".to_string(),
+            JobState::Stopped(sig) => describe(sig),
+            JobState::Done(0) => "Done".to_string(),
+            JobState::Done(status) if status > 128 => describe(status - 128),
+            JobState::Done(status) => format!("Exit {}", status),
+        }
+    }
+}
+
+/// The shell's jobs and what it needs to hand the terminal to them.
+#[derive(Default)]
+pub struct Jobs {
+    // job control is on: jobs get process groups and the terminal
+    pub control: bool,
+    pub list: Vec<Job>,
+    // job ids, the most recently started, stopped or resumed last; that
+    // one is the current job `%+`, the one before it `%-`
+    order: Vec<usize>,
+    // the pipeline running in the foreground, named if it gets stopped
+    pub foreground: String,
+    shell_pgid: i32,
+    // a copy of the terminal that redirections cannot move
+    tty: i32,
+    // the shell's own terminal settings
+    tmodes: Option<libc::termios>,
+}
+
+impl Jobs {
+    /// Take charge of the terminal: put the shell in a process group of its
+    /// own in the foreground and ignore the signals meant for jobs. Does
+    /// nothing if stdin is not a terminal.
+    pub fn enable(&mut self) {
+        unsafe {
+            let tty = libc::fcntl(0, libc::F_DUPFD_CLOEXEC, 255);
+            if tty == -1 || libc::isatty(tty) != 1 {
+                return;
+            }
+            // started in the background: stop until someone brings us to
+            // the foreground
+            loop {
+                let owner = libc::tcgetpgrp(tty);
+                if owner == -1 || owner == libc::getpgrp() {
+                    break;
+                }
+                libc::kill(-libc::getpgrp(), libc::SIGTTIN);
+            }
+            for sig in [libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU] {
+                libc::signal(sig, libc::SIG_IGN);
+            }
+            // fails for a session leader, which has its own group already
+            libc::setpgid(0, 0);
+            self.shell_pgid = libc::getpgrp();
+            libc::tcsetpgrp(tty, self.shell_pgid);
+            let mut tmodes: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(tty, &mut tmodes) == 0 {
+                self.tmodes = Some(tmodes);
+            }
+            self.tty = tty;
+        }
+        self.control = true;
+    }
+
+    /// The terminal goes to the process group `pgid`.
+    pub fn give_terminal(&self, pgid: i32) {
+        if self.control {
+            unsafe { libc::tcsetpgrp(self.tty, pgid) };
+        }
+    }
+
+    /// Get the terminal back once the foreground job finished or stopped,
+    /// with the shell's settings; a stopped job keeps the ones it had.
+    fn take_terminal(&mut self, job: &mut Job) {
+        if !self.control {
+            return;
+        }
+        unsafe {
+            libc::tcsetpgrp(self.tty, self.shell_pgid);
+            let mut tmodes: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(self.tty, &mut tmodes) == 0 {
+                match job.state() {
+                    JobState::Stopped(_) => job.tmodes = Some(tmodes),
+                    // a job that ends well may have changed them on purpose,
+                    // like `stty`
+                    JobState::Done(0) => self.tmodes = Some(tmodes),
+                    _ => {}
+                }
+            }
+            if let Some(tmodes) = &self.tmodes {
+                libc::tcsetattr(self.tty, libc::TCSADRAIN, tmodes);
+            }
+        }
+    }
+
+    /// The terminal, for `enter_group` in a child about to exec.
+    pub fn tty(&self) -> i32 {
+        self.tty
+    }
+
+
+    /// Add a job to the table and make it the current one. Returns its id.
+    pub fn add(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.list.iter().map(|j| j.id).max().unwrap_or(0) + 1;
+        }
+        let id = job.id;
+        let at = self.list.iter().position(|j| j.id > id).unwrap_or(self.list.len());
+        self.list.insert(at, job);
+        self.touch(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        self.order.retain(|&j| j != id);
+        let idx = self.list.iter().position(|j| j.id == id)?;
+        Some(self.list.remove(idx))
+    }
+
+    pub fn get(&mut self, id: usize) -> Option<&mut Job> {
+        self.list.iter_mut().find(|j| j.id == id)
+    }
+
+    /// The job with process `pid` in it.
+    pub fn with_pid(&self, pid: i32) -> Option<usize> {
+        self.list.iter().find(|j| j.pids().any(|p| p == pid)).map(|j| j.id)
+    }
+
+    /// Make a job the current one.
+    pub fn touch(&mut self, id: usize) {
+        self.order.retain(|&j| j != id);
+        self.order.push(id);
+    }
+
+    /// `+` for the current job, `-` for the previous one.
+    pub fn mark(&self, id: usize) -> char {
+        match self.order.iter().rev().position(|&j| j == id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        }
+    }
+
+    /// `[1]+  Running                 sleep 10 &`, with the process group
+    /// after the mark when `long`.
+    pub fn format(&self, job: &Job, long: bool) -> String {
+        let text = match job.state() {
+            JobState::Running => format!("{} &", job.text),
+            _ => job.text.clone(),
+        };
+        let mark = self.mark(job.id);
+        if long {
+            format!("[{}]{} {} {:<24}{}", job.id, mark, job.pgid, job.describe_state(), text)
+        } else {
+            format!("[{}]{}  {:<24}{}", job.id, mark, job.describe_state(), text)
+        }
+    }
+
+    /// Find out, without waiting, what the jobs have been up to.
+    pub fn update(&mut self) {
+        for job in self.list.iter_mut().filter(|j| !j.inherited) {
+            job.poll(libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED);
+        }
+    }
+
+    /// Report jobs that finished or stopped since the last prompt, and
+    /// forget the finished ones.
+    pub fn notify(&mut self) {
+        self.update();
+        for job in self.list.iter().filter(|j| j.changed && j.state() != JobState::Running) {
+            eprintln!("{}", self.format(job, false));
+        }
+        self.reported();
+    }
+
+    /// Everything there was to say about the jobs has been said: forget
+    /// the finished ones.
+    pub fn reported(&mut self) {
+        for job in &mut self.list {
+            job.changed = false;
+        }
+        let done: Vec<usize> = self.list.iter().filter(|j| matches!(j.state(), JobState::Done(_))).map(|j| j.id).collect();
+        for id in done {
+            self.remove(id);
+        }
+    }
+
+    /// The job a `%` spec names: `%n`, `%+` or `%%` the current job, `%-`
+    /// the previous one, `%text` the one whose command starts with text and
+    /// `%?text` the one containing it. Without `%` it is a job number.
+    pub fn find(&self, spec: &str) -> Result<usize, String> {
+        let no_such = || format!("{}: no such job", if spec.is_empty() { "current" } else { spec });
+        let body = spec.strip_prefix('%').unwrap_or(spec);
+        let from_end = |n: usize| self.order.iter().rev().nth(n).copied().ok_or_else(no_such);
+        match body {
+            "" | "+" | "%" => from_end(0),
+            "-" => from_end(1),
+            _ if body.chars().all(|c| c.is_ascii_digit()) => {
+                let id: usize = body.parse().map_err(|_| no_such())?;
+                self.list.iter().find(|j| j.id == id).map(|j| j.id).ok_or_else(no_such)
+            }
+            _ => {
+                let found: Vec<usize> = match body.strip_prefix('?') {
+                    Some(text) => self.list.iter().filter(|j| j.text.contains(text)).map(|j| j.id).collect(),
+                    None => self.list.iter().filter(|j| j.text.starts_with(body)).map(|j| j.id).collect(),
+                };
+                match found.as_slice() {
+                    [] => Err(no_such()),
+                    [id] => Ok(*id),
+                    _ => Err(format!("{}: ambiguous job spec", spec)),
+                }
+            }
+        }
+    }
+}
+
+/// In a child just forked from the shell: it is not the interactive shell,
+/// so it does no job control and has no traps, and with job control on it
+/// joins a job as `enter_group` does.
+pub fn enter_child(shell: &mut Shell, group: Option<(i32, bool)>) {
+    if shell.jobs.control {
+        let (pgid, foreground) = group.unwrap_or((-1, false));
+        enter_group(shell.jobs.tty, pgid, foreground);
+    }
+    // the table stays, so `jobs | cat` lists the parent's jobs; they and
+    // the terminal are still the parent's to look after
+    shell.jobs.control = false;
+    shell.jobs.tty = -1;
+    for job in &mut shell.jobs.list {
+        job.inherited = true;
+    }
+    reset_traps(shell);
+}
+
+/// In a child of a shell with job control: join process group `pgid` (`0`
+/// starts a new one with the child as leader, `-1` stays in the shell's),
+/// take the terminal `tty` if the job runs in the foreground, and get the
+/// default handling back for the signals the shell ignores or catches. Only
+/// makes system calls, so it can run between fork and exec.
+pub fn enter_group(tty: i32, pgid: i32, foreground: bool) {
+    unsafe {
+        if pgid != -1 {
+            libc::setpgid(0, pgid);
+        }
+        if foreground {
+            libc::tcsetpgrp(tty, libc::getpgrp());
+        }
+        for sig in [libc::SIGINT, libc::SIGQUIT, libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU] {
+            libc::signal(sig, libc::SIG_DFL);
+        }
+    }
+}
+
+/// Wait for a job running in the foreground and take the terminal back
+/// after. A job that gets stopped goes in the table and counts as killed by
+/// the signal that stopped it.
+pub fn wait_foreground(shell: &mut Shell, mut job: Job) -> i32 {
+    // a Ctrl-C reaches the job too, so keep waiting for what it does
+    while job.state() == JobState::Running {
+        job.poll(libc::WUNTRACED);
+    }
+    shell.jobs.take_terminal(&mut job);
+    match job.state() {
+        JobState::Stopped(sig) => {
+            // reported right here, not again at the prompt
+            job.changed = false;
+            let id = shell.jobs.add(job);
+            if let Some(job) = shell.jobs.list.iter().find(|j| j.id == id) {
+                eprintln!("\n{}", shell.jobs.format(job, false));
+            }
+            128 + sig
+        }
+        JobState::Done(status) => {
+            // it got the Ctrl-C meant for the whole line
+            if status == 128 + libc::SIGINT && shell.jobs.control {
+                deliver(libc::SIGINT);
+            }
+            status
+        }
+        JobState::Running => 0,
+    }
+}
+
+/// `fg`: give a job from the table the terminal, with the settings it had
+/// when it stopped, let it go on and wait for it.
+pub fn resume_foreground(shell: &mut Shell, mut job: Job) -> i32 {
+    shell.jobs.give_terminal(job.pgid);
+    if let Some(tmodes) = job.tmodes.take().filter(|_| shell.jobs.control) {
+        unsafe { libc::tcsetattr(shell.jobs.tty, libc::TCSADRAIN, &tmodes) };
+    }
+    if let Err(e) = job.resume() {
+        eprintln!("fg: {}", e);
+    }
+    wait_foreground(shell, job)
+}
@@ -0,0 +1,108 @@
+use crate::exec::job::*;
+use crate::exec::pipeline::*;
+use crate::exec::signal::*;
+use crate::exec::trap::*;
+use crate::parsing::ast::*;
+use crate::variables::shell::*;
+use std::ffi::CString;
+use std::io::{self, Write};
+
+/// Run a command list and return the status of the last pipeline that ran.
+/// Every pipeline that runs updates `$?`. A `break` or `continue` stops the
+/// list early, and so does Ctrl-C.
+pub fn run_list(shell: &mut Shell, list: &List) -> i32 {
+    for item in &list.items {
+        if item.background {
+            run_background(shell, item);
+        } else {
+            run_and_or(shell, item);
+        }
+        run_pending_traps(shell);
+        if shell.flow != Flow::Normal || interrupted() {
+            break;
+        }
+    }
+    shell.last_status
+}
+
+/// Run an and-or list, skipping `&&`/`||` branches based on the status of
+/// the pipeline before them. The ERR trap runs when the last pipeline runs
+/// and fails.
+fn run_and_or(shell: &mut Shell, item: &AndOrList) {
+    shell.last_status = run_pipeline(shell, &item.first);
+    // the last pipeline, if it ran
+    let mut last = Some(&item.first);
+    for (op, pipeline) in &item.rest {
+        if shell.flow != Flow::Normal {
+            return;
+        }
+        let run = match op {
+            AndOr::And => shell.last_status == 0,
+            AndOr::Or => shell.last_status != 0,
+        };
+        last = run.then_some(pipeline);
+        if run {
+            shell.last_status = run_pipeline(shell, pipeline);
+        }
+    }
+    if shell.last_status != 0 && last.is_some_and(|p| !p.negated) {
+        run_err_trap(shell);
+    }
+}
+
+/// Start an and-or list ended by `&` in a forked copy of the shell and go on
+/// without waiting for it. It becomes a job; without job control it reads
+/// from /dev/null and ignores Ctrl-C, leaving the terminal to the
+/// foreground.
+fn run_background(shell: &mut Shell, item: &AndOrList) {
+    // anything still buffered would otherwise be printed by the child too
+    let _ = io::stdout().flush();
+
+    let pid = unsafe { libc::fork() };
+    if pid == -1 {
+        eprintln!("0-shell: fork: {}", io::Error::last_os_error());
+        shell.last_status = 1;
+        return;
+    }
+    if pid == 0 {
+        let control = shell.jobs.control;
+        enter_child(shell, Some((0, false)));
+        if !control {
+            // Ctrl-C is for the foreground, as POSIX has it
+            unsafe {
+                libc::signal(libc::SIGINT, libc::SIG_IGN);
+                libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+            }
+            let null = CString::new("/dev/null").unwrap_or_default();
+            unsafe {
+                let fd = libc::open(null.as_ptr(), libc::O_RDONLY);
+                if fd != -1 {
+                    libc::dup2(fd, 0);
+                    libc::close(fd);
+                }
+            }
+        }
+        // a lone command can exec straight away
+        let status = match (&item.first.commands[..], item.rest.is_empty() && !item.first.negated) {
+            ([command], true) => run_stage(shell, command),
+            _ => {
+                run_and_or(shell, item);
+                shell.last_status
+            }
+        };
+        let _ = io::stdout().flush();
+        unsafe { libc::_exit(status) };
+    }
+
+    let own_group = shell.jobs.control;
+    if own_group {
+        unsafe { libc::setpgid(pid, pid) };
+    }
+    shell.jobs.update();
+    let id = shell.jobs.add(Job::new(pid, &[pid], &item.text(), own_group));
+    if shell.interactive {
+        eprintln!("[{}] {}", id, pid);
+    }
+    shell.last_bg_pid = Some(pid);
+    shell.last_status = 0;
+}
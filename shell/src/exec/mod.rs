@@ -0,0 +1,12 @@
+pub mod command;
+pub mod dispatch;
+pub mod external;
+pub mod job;
+pub mod list;
+pub mod pipeline;
+pub mod prompt;
+pub mod redirect;
+pub mod script;
+pub mod signal;
+pub mod substitution;
+pub mod trap;
\ No newline at end of file
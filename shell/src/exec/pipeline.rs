@@ -0,0 +1,165 @@
+use crate::exec::command::*;
+use crate::exec::dispatch::*;
+use crate::exec::external::*;
+use crate::exec::job::*;
+use crate::exec::redirect::*;
+use crate::exec::signal::*;
+use crate::parsing::expand::*;
+use crate::parsing::ast::*;
+use crate::variables::shell::*;
+use std::io::{self, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command as ProcessCommand, ExitStatus};
+
+/// Run a pipeline and return the exit status of its last command, inverted
+/// by a leading `!`.
+///
+/// A single command runs directly in the shell so builtins like `cd` keep
+/// their effect. With more than one stage every stage is forked into its own
+/// process, stdout of each stage feeding stdin of the next one.
+pub fn run_pipeline(shell: &mut Shell, pipeline: &Pipeline) -> i32 {
+    // the rest of a line Ctrl-C stopped does not run
+    if interrupted() {
+        return 128 + libc::SIGINT;
+    }
+    shell.jobs.foreground = pipeline.text.clone();
+    let status = if pipeline.commands.len() == 1 {
+        execute(shell, &pipeline.commands[0])
+    } else {
+        run_stages(shell, &pipeline.commands)
+    };
+    if pipeline.negated { (status == 0) as i32 } else { status }
+}
+
+fn run_stages(shell: &mut Shell, pipeline: &[Command]) -> i32 {
+    // anything still buffered would otherwise be printed by every child too
+    let _ = io::stdout().flush();
+
+    let mut pids: Vec<i32> = Vec::with_capacity(pipeline.len());
+    let mut prev_read: Option<i32> = None;
+    // with job control the stages form a process group led by the first
+    let own_group = shell.jobs.control;
+
+    for (idx, command) in pipeline.iter().enumerate() {
+        let last = idx == pipeline.len() - 1;
+
+        let mut fds = [-1; 2];
+        if !last && unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            eprintln!("0-shell: pipe: {}", io::Error::last_os_error());
+            break;
+        }
+
+        let pid = unsafe { libc::fork() };
+        if pid == -1 {
+            eprintln!("0-shell: fork: {}", io::Error::last_os_error());
+            break;
+        }
+
+        if pid == 0 {
+            enter_child(shell, Some((pids.first().copied().unwrap_or(0), true)));
+            unsafe {
+                if let Some(fd) = prev_read {
+                    libc::dup2(fd, 0);
+                    libc::close(fd);
+                }
+                if !last {
+                    libc::dup2(fds[1], 1);
+                    libc::close(fds[1]);
+                    libc::close(fds[0]);
+                }
+                // the shell ignores SIGPIPE; a stage whose reader went away should die
+                libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+            }
+            let status = run_stage(shell, command);
+            let _ = io::stdout().flush();
+            unsafe { libc::_exit(status) };
+        }
+
+        if own_group {
+            // in the parent too, so the group exists whichever runs first
+            let pgid = pids.first().copied().unwrap_or(pid);
+            unsafe { libc::setpgid(pid, pgid) };
+            if pids.is_empty() {
+                shell.jobs.give_terminal(pid);
+            }
+        }
+        pids.push(pid);
+        unsafe {
+            if let Some(fd) = prev_read {
+                libc::close(fd);
+            }
+            if !last {
+                libc::close(fds[1]);
+            }
+        }
+        prev_read = if last { None } else { Some(fds[0]) };
+    }
+
+    if let Some(fd) = prev_read {
+        unsafe { libc::close(fd) };
+    }
+
+    let Some(&pgid) = pids.first() else {
+        return 1;
+    };
+    let text = shell.jobs.foreground.clone();
+    wait_foreground(shell, Job::new(pgid, &pids, &text, own_group))
+}
+
+/// Body of a forked pipeline stage: functions, builtins and compound
+/// commands run in place, anything else replaces the child process through
+/// exec.
+pub fn run_stage(shell: &mut Shell, command: &Command) -> i32 {
+    let Command::Simple(cmd) = command else {
+        return execute(shell, command);
+    };
+    let expanded = match expand_command(shell, cmd) {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            eprintln!("0-shell: {}", e);
+            return 1;
+        }
+    };
+    // the child exits right after, so the saved copies are never needed
+    if let Err(e) = apply_redirects(&expanded.redirects) {
+        eprintln!("0-shell: {}", e);
+        return 1;
+    }
+    shell.push_temp_vars(&expanded.assignments);
+
+    let argv = &expanded.argv;
+    let Some(name) = argv.first() else {
+        return 0;
+    };
+    if is_builtin(name) || shell.functions.contains_key(name) {
+        return run_command(shell, argv);
+    }
+
+    let path = match find_in_path(name) {
+        Some(p) => p,
+        None => {
+            eprintln!("command not found: {}", name);
+            return STATUS_NOT_FOUND;
+        }
+    };
+
+    let err = ProcessCommand::new(path).arg0(name).args(&argv[1..]).exec();
+    eprintln!("{}: {}", name, err);
+    match err.kind() {
+        io::ErrorKind::NotFound => STATUS_NOT_FOUND,
+        _ => STATUS_NOT_EXECUTABLE,
+    }
+}
+
+/// Wait for a child and return its exit status.
+pub fn wait_pid(pid: i32) -> i32 {
+    let mut raw = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut raw, 0) } != -1 {
+            return status_code(ExitStatus::from_raw(raw));
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return 1;
+        }
+    }
+}
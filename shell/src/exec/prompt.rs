@@ -0,0 +1,172 @@
+use crate::variables::shell::*;
+use chrono::Local;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The current directory with the home directory shown as `~`.
+fn tilde_cwd(shell: &Shell) -> String {
+    let Ok(cwd) = env::current_dir() else {
+        return String::new();
+    };
+    let cwd = cwd.display().to_string();
+    match shell.get_var("HOME").filter(|h| !h.is_empty() && *h != "/") {
+        Some(home) if cwd == home => "~".to_string(),
+        Some(home) if cwd.starts_with(home) && cwd[home.len()..].starts_with('/') => format!("~{}", &cwd[home.len()..]),
+        _ => cwd,
+    }
+}
+
+fn host_name() -> String {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return String::new();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).to_string()
+}
+
+fn user_name(shell: &Shell) -> String {
+    match users::get_current_username() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => shell.get_var("USER").unwrap_or_default().to_string(),
+    }
+}
+
+/// The `HEAD` file of the git repository `dir` is in, if any. A `.git`
+/// file (worktrees, submodules) says where the real one is.
+fn git_head(dir: &Path) -> Option<PathBuf> {
+    for dir in dir.ancestors() {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            return Some(git.join("HEAD"));
+        }
+        if let Ok(text) = fs::read_to_string(&git) {
+            let target = text.trim().strip_prefix("gitdir:")?.trim();
+            return Some(dir.join(target).join("HEAD"));
+        }
+    }
+    None
+}
+
+/// The checked out branch, or the short commit id when detached. Read from
+/// `.git/HEAD` without running git.
+fn git_branch() -> String {
+    let head = env::current_dir().ok().and_then(|cwd| git_head(&cwd)).and_then(|head| fs::read_to_string(head).ok());
+    let Some(head) = head else {
+        return String::new();
+    };
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => reference.trim().trim_start_matches("refs/heads/").to_string(),
+        None => head.chars().take(7).collect(),
+    }
+}
+
+/// Expand the backslash escapes of a `PS1` or `PS2` value:
+///
+/// - `\u` user, `\h` host up to the first `.`, `\H` the whole host name
+/// - `\w` current directory with `~` for home, `\W` its last part
+/// - `\?` exit status of the last command, `\$` `#` for root and `$` otherwise
+/// - `\t` `HH:MM:SS`, `\T` the same in 12-hour time, `\@` `HH:MM am`,
+///   `\A` `HH:MM`, `\d` `Tue May 26`, `\D{format}` strftime
+/// - `\g` the git branch, empty outside a repository
+/// - `\s` the shell's name, `\_` (in `PS2`) what is left open, like `quote`
+/// - `\e` escape, for colours (`\e[32m`), `\a` bell, `\n` newline, `\nnn`
+///   an octal character, `\\` a backslash
+/// - `\[` and `\]` are accepted and dropped; the editor already knows
+///   escape sequences take no room
+pub fn expand_prompt(shell: &Shell, template: &str, open: &str) -> String {
+    let now = Local::now();
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            out.push('\\');
+            break;
+        };
+        match escape {
+            'u' => out.push_str(&user_name(shell)),
+            'h' => out.push_str(host_name().split('.').next().unwrap_or("")),
+            'H' => out.push_str(&host_name()),
+            'w' => out.push_str(&tilde_cwd(shell)),
+            'W' => {
+                let cwd = tilde_cwd(shell);
+                match cwd.rsplit_once('/') {
+                    Some((_, last)) if !last.is_empty() => out.push_str(last),
+                    _ => out.push_str(&cwd),
+                }
+            }
+            '?' => out.push_str(&shell.last_status.to_string()),
+            '$' => out.push(if unsafe { libc::geteuid() } == 0 { '#' } else { '$' }),
+            't' => out.push_str(&now.format("%H:%M:%S").to_string()),
+            'T' => out.push_str(&now.format("%I:%M:%S").to_string()),
+            '@' => out.push_str(&now.format("%I:%M %P").to_string()),
+            'A' => out.push_str(&now.format("%H:%M").to_string()),
+            'd' => out.push_str(&now.format("%a %b %e").to_string()),
+            'D' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let format: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let format = if format.is_empty() { "%X" } else { format.as_str() };
+                // an unknown `%` sequence fails to format instead of panicking
+                let mut text = String::new();
+                if std::fmt::write(&mut text, format_args!("{}", now.format(format))).is_ok() {
+                    out.push_str(&text);
+                }
+            }
+            'g' => out.push_str(&git_branch()),
+            's' => out.push_str("0-shell"),
+            '_' => out.push_str(open),
+            'e' => out.push('\x1b'),
+            'a' => out.push('\x07'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            '\\' => out.push('\\'),
+            '[' | ']' => {}
+            '0'..='7' => {
+                let mut code = escape.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            code = code * 8 + d;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                out.extend(char::from_u32(code));
+            }
+            other => {
+                out.push('\\');
+                out.push(other);
+            }
+        }
+    }
+    out
+}
+
+/// The prompt shown before a command: `PS1`, or the current directory and
+/// `$ ` when it is not set.
+pub fn primary_prompt(shell: &Shell) -> String {
+    match shell.get_var("PS1") {
+        Some(ps1) => expand_prompt(shell, ps1, ""),
+        None => match env::current_dir() {
+            Ok(path) => format!("{}$ ", path.display()),
+            Err(_) => "$ ".to_string(),
+        },
+    }
+}
+
+/// The prompt for a line continuing an unfinished command. `prompt` is
+/// what the parser asks for, like `quote> `; `PS2` replaces it when set,
+/// with `\_` standing for the part before `> `.
+pub fn continuation_prompt(shell: &Shell, prompt: &str) -> String {
+    match shell.get_var("PS2") {
+        Some(ps2) => expand_prompt(shell, ps2, prompt.trim_end_matches("> ")),
+        None => prompt.to_string(),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::parsing::ast::*;
+use std::ffi::CString;
+use std::io::{self, Write};
+
+/// A redirection whose target has been expanded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirection {
+    pub fd: i32,
+    pub kind: RedirectKind,
+    pub target: String,
+}
+
+/// File descriptors replaced by `apply_redirects`, with the copies needed to
+/// put them back once the command is done.
+pub struct SavedFds {
+    saved: Vec<(i32, i32)>,
+}
+
+fn open_target(target: &str, flags: i32) -> Result<i32, String> {
+    let path = CString::new(target).map_err(|_| format!("{}: invalid file name", target))?;
+    let fd = unsafe { libc::open(path.as_ptr(), flags | libc::O_CLOEXEC, 0o644) };
+    if fd == -1 {
+        let err = io::Error::last_os_error();
+        let msg = match err.kind() {
+            io::ErrorKind::NotFound => "No such file or directory".to_string(),
+            io::ErrorKind::PermissionDenied => "Permission denied".to_string(),
+            _ => err.to_string(),
+        };
+        return Err(format!("{}: {}", target, msg));
+    }
+    Ok(fd)
+}
+
+impl SavedFds {
+    fn save(&mut self, fd: i32) {
+        if self.saved.iter().any(|&(orig, _)| orig == fd) {
+            return;
+        }
+        // keep the copy above the low fds and out of the commands we start
+        let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 10) };
+        self.saved.push((fd, copy));
+    }
+
+    fn replace(&mut self, fd: i32, with: i32) -> Result<(), String> {
+        self.save(fd);
+        if unsafe { libc::dup2(with, fd) } == -1 {
+            return Err(format!("{}: {}", fd, io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    fn redirect(&mut self, r: &Redirection) -> Result<(), String> {
+        let open_flags = match r.kind {
+            RedirectKind::Input => libc::O_RDONLY,
+            RedirectKind::Output | RedirectKind::Both => libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+            RedirectKind::Append | RedirectKind::BothAppend => libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND,
+            RedirectKind::Dup(src) => return self.replace(r.fd, src),
+            RedirectKind::Close => {
+                self.save(r.fd);
+                unsafe { libc::close(r.fd) };
+                return Ok(());
+            }
+        };
+
+        let file = open_target(&r.target, open_flags)?;
+        let mut result = self.replace(r.fd, file);
+        if result.is_ok() && matches!(r.kind, RedirectKind::Both | RedirectKind::BothAppend) {
+            result = self.replace(2, file);
+        }
+        unsafe { libc::close(file) };
+        result
+    }
+
+    /// Put every replaced descriptor back the way it was.
+    pub fn restore(self) {
+        let _ = io::stdout().flush();
+        for &(fd, copy) in self.saved.iter().rev() {
+            unsafe {
+                if copy == -1 {
+                    libc::close(fd);
+                } else {
+                    libc::dup2(copy, fd);
+                    libc::close(copy);
+                }
+            }
+        }
+    }
+}
+
+/// Apply redirections in order to the shell's own file descriptors.
+/// On error nothing stays redirected and the message is returned.
+pub fn apply_redirects(redirects: &[Redirection]) -> Result<SavedFds, String> {
+    let mut saved = SavedFds { saved: Vec::new() };
+    if redirects.is_empty() {
+        return Ok(saved);
+    }
+
+    // output printed before the redirection belongs to the old stdout
+    let _ = io::stdout().flush();
+    for r in redirects {
+        if let Err(e) = saved.redirect(r) {
+            saved.restore();
+            return Err(e);
+        }
+    }
+    Ok(saved)
+}
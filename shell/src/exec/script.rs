@@ -0,0 +1,90 @@
+use crate::exec::list::*;
+use crate::exec::prompt::*;
+use crate::exec::signal::*;
+use crate::exec::trap::*;
+use crate::history::bang::*;
+use crate::parsing::split_save::*;
+use crate::variables::shell::*;
+
+/// Read, parse and run commands from `input` until it runs out. Unless
+/// someone is typing, a syntax error stops it too, and so do Ctrl-C and
+/// `return` in a sourced file.
+pub fn run_input(shell: &mut Shell, input: &mut Input) {
+    loop {
+        run_pending_traps(shell);
+        if input.interactive() {
+            shell.jobs.notify();
+        }
+        // only worked out when it is shown: `\g` reads files
+        let prompt = if input.interactive() { primary_prompt(shell) } else { String::new() };
+        let Some(mut source) = input.next_line(&prompt, shell) else {
+            break;
+        };
+
+        if source.trim().is_empty() {
+            continue;
+        }
+        // `!!` and friends, before anything else looks at the line
+        if input.interactive() {
+            match expand_history(&source, &shell.history) {
+                Ok(Some(expanded)) => {
+                    println!("{}", expanded);
+                    source = expanded;
+                }
+                Ok(None) => {}
+                Err(reference) => {
+                    eprintln!("0-shell: {}: event not found", reference);
+                    shell.last_status = 1;
+                    continue;
+                }
+            }
+        }
+
+        let list = split_save(input, &mut source, shell);
+        if input.interactive() {
+            shell.add_history(&source);
+        }
+        match list {
+            Some(list) => {
+                if input.interactive() {
+                    take_interrupt();
+                }
+                run_list(shell, &list);
+                // back to a fresh prompt, below the `^C`
+                if input.interactive() && take_interrupt() {
+                    shell.last_status = 128 + libc::SIGINT;
+                    eprintln!();
+                }
+            }
+            None => {
+                // bash uses 2 for syntax errors too, and a script stops there
+                shell.last_status = 2;
+                if !input.interactive() {
+                    break;
+                }
+            }
+        }
+        if shell.flow == Flow::Return || interrupted() {
+            break;
+        }
+    }
+}
+
+/// Run the text of a sourced file in the current shell, so `cd`, variables
+/// and functions stay. Non-empty `args` replace the positional parameters
+/// while it runs.
+pub fn run_sourced(shell: &mut Shell, text: &str, args: &[String]) -> i32 {
+    let saved_params = (!args.is_empty()).then(|| std::mem::replace(&mut shell.positional, args.to_vec()));
+    shell.source_depth += 1;
+
+    run_input(shell, &mut Input::text(text));
+
+    shell.source_depth -= 1;
+    if shell.flow == Flow::Return {
+        shell.flow = Flow::Normal;
+    }
+    if let Some(params) = saved_params {
+        shell.positional = params;
+    }
+    shell.last_status
+}
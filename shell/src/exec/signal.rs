@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// set by the SIGINT handler, cleared once the prompt is back
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+// Ctrl-C stops the command line even when SIGINT has a trap
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+// one bit per signal that has a `trap` command
+static TRAPPED: AtomicU64 = AtomicU64::new(0);
+// one bit per trapped signal that arrived and whose command has not run yet
+static PENDING: AtomicU64 = AtomicU64::new(0);
+
+fn bit(sig: i32) -> u64 {
+    1u64.checked_shl(sig as u32).unwrap_or(0)
+}
+
+extern "C" fn on_signal(sig: libc::c_int) {
+    let trapped = TRAPPED.load(Ordering::SeqCst) & bit(sig) != 0;
+    if trapped {
+        PENDING.fetch_or(bit(sig), Ordering::SeqCst);
+    }
+    if sig == libc::SIGINT && (!trapped || INTERACTIVE.load(Ordering::SeqCst)) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Handle `sig` in the shell: run its trap command if it has one, and for
+/// SIGINT set the flag `interrupted` reads unless a script traps it.
+/// Installed without `SA_RESTART` so a builtin blocked reading the terminal
+/// wakes up.
+pub fn catch_signal(sig: i32) {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        action.sa_flags = 0;
+        libc::sigaction(sig, &action, std::ptr::null_mut());
+    }
+}
+
+/// Keep the interactive shell alive on Ctrl-C and Ctrl-\. SIGINT only sets a
+/// flag that loops and builtins look at; SIGQUIT is ignored.
+pub fn catch_interrupts() {
+    INTERACTIVE.store(true, Ordering::SeqCst);
+    catch_signal(libc::SIGINT);
+    unsafe { libc::signal(libc::SIGQUIT, libc::SIG_IGN) };
+}
+
+/// Whether Ctrl-C was pressed since the command line started: whatever is
+/// running should stop.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Set the flag `interrupted` reads, as Ctrl-C does.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Act as if the shell got `sig`, for one that went to a foreground job
+/// instead: after a Ctrl-C that killed the job the rest of the line should
+/// stop too, and a trap on SIGINT should run.
+pub fn deliver(sig: i32) {
+    on_signal(sig);
+}
+
+/// Whether Ctrl-C was pressed, forgetting it.
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+/// Say whether `sig` has a trap command, so its handler knows what to do.
+pub fn set_trapped(sig: i32, trapped: bool) {
+    if trapped {
+        TRAPPED.fetch_or(bit(sig), Ordering::SeqCst);
+    } else {
+        TRAPPED.fetch_and(!bit(sig), Ordering::SeqCst);
+        PENDING.fetch_and(!bit(sig), Ordering::SeqCst);
+    }
+}
+
+/// The trapped signals that arrived since the last call, lowest first.
+pub fn take_pending() -> Vec<i32> {
+    let pending = PENDING.swap(0, Ordering::SeqCst);
+    (1..64).filter(|&sig| pending & bit(sig) != 0).collect()
+}
@@ -0,0 +1,69 @@
+use crate::exec::job::*;
+use crate::exec::list::*;
+use crate::exec::pipeline::*;
+use crate::parsing::parser::*;
+use crate::variables::shell::*;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::FromRawFd;
+
+/// Run a command line in a forked copy of the shell and return what it wrote
+/// to stdout without the trailing newlines. `$?` becomes its exit status.
+pub fn command_output(shell: &mut Shell, source: &str) -> String {
+    // anything still buffered would otherwise end up in the output too
+    let _ = io::stdout().flush();
+
+    let mut fds = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+        eprintln!("0-shell: pipe: {}", io::Error::last_os_error());
+        return String::new();
+    }
+
+    let pid = unsafe { libc::fork() };
+    if pid == -1 {
+        eprintln!("0-shell: fork: {}", io::Error::last_os_error());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        return String::new();
+    }
+
+    if pid == 0 {
+        enter_child(shell, None);
+        unsafe {
+            libc::close(fds[0]);
+            libc::dup2(fds[1], 1);
+            libc::close(fds[1]);
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        }
+        let status = match parse_program(source, &shell.aliases) {
+            Ok(list) => run_list(shell, &list),
+            Err(e) => {
+                e.report(source);
+                2
+            }
+        };
+        let _ = io::stdout().flush();
+        unsafe { libc::_exit(status) };
+    }
+
+    unsafe { libc::close(fds[1]) };
+    let mut output = Vec::new();
+    // SAFETY: the read end is ours alone and closed when `reader` drops
+    let mut reader = unsafe { File::from_raw_fd(fds[0]) };
+    if let Err(e) = reader.read_to_end(&mut output) {
+        eprintln!("0-shell: command substitution: {}", e);
+    }
+    drop(reader);
+
+    let status = wait_pid(pid);
+    shell.last_status = status;
+    shell.subst_status = Some(status);
+
+    let mut text = String::from_utf8_lossy(&output).into_owned();
+    while text.ends_with('\n') {
+        text.pop();
+    }
+    text
+}
@@ -0,0 +1,149 @@
+use crate::exec::job::*;
+use crate::exec::script::*;
+use crate::exec::signal::*;
+use crate::variables::shell::*;
+use std::collections::BTreeMap;
+
+/// What a trap command waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrapCondition {
+    // the shell exits
+    Exit,
+    Signal(i32),
+    // a command fails
+    Err,
+}
+
+impl TrapCondition {
+    /// `EXIT` (or `0`), `ERR`, or a signal as `INT`, `SIGINT` or `2`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "EXIT" | "0" => Some(TrapCondition::Exit),
+            "ERR" => Some(TrapCondition::Err),
+            _ => signal_number(name).filter(|&sig| sig > 0).map(TrapCondition::Signal),
+        }
+    }
+
+    /// The name `trap -p` shows.
+    pub fn name(self) -> String {
+        match self {
+            TrapCondition::Exit => "EXIT".to_string(),
+            TrapCondition::Err => "ERR".to_string(),
+            TrapCondition::Signal(sig) => match SIGNALS.iter().find(|(n, _, _)| *n == sig) {
+                Some((_, name, _)) => format!("SIG{}", name),
+                None => sig.to_string(),
+            },
+        }
+    }
+}
+
+/// The commands set with `trap`. An empty one means the signal is ignored.
+#[derive(Default)]
+pub struct Traps {
+    pub actions: BTreeMap<TrapCondition, String>,
+    // a trap command is running, so a failure in it does not fire ERR
+    running: bool,
+}
+
+/// What the shell does with `sig` when it has no trap: an interactive shell
+/// catches Ctrl-C and ignores Ctrl-\ and the job control stops.
+fn restore_signal(shell: &Shell, sig: i32) {
+    let job_stop = matches!(sig, libc::SIGTSTP | libc::SIGTTIN | libc::SIGTTOU);
+    if sig == libc::SIGINT && shell.interactive {
+        catch_signal(sig);
+        return;
+    }
+    let handler = if sig == libc::SIGQUIT && shell.interactive || job_stop && shell.jobs.control {
+        libc::SIG_IGN
+    } else {
+        libc::SIG_DFL
+    };
+    unsafe { libc::signal(sig, handler) };
+}
+
+/// Set the command for a condition: `None` puts back what the shell does
+/// without one, an empty command ignores the signal.
+pub fn set_trap(shell: &mut Shell, condition: TrapCondition, action: Option<&str>) {
+    if let TrapCondition::Signal(sig) = condition {
+        match action {
+            None => {
+                set_trapped(sig, false);
+                restore_signal(shell, sig);
+            }
+            Some("") => {
+                set_trapped(sig, false);
+                unsafe { libc::signal(sig, libc::SIG_IGN) };
+            }
+            Some(_) => {
+                set_trapped(sig, true);
+                catch_signal(sig);
+            }
+        }
+    }
+    match action {
+        Some(action) => shell.traps.actions.insert(condition, action.to_string()),
+        None => shell.traps.actions.remove(&condition),
+    };
+}
+
+/// Run the command for a condition, if it has one. `$?` is the same after
+/// it as before, and a Ctrl-C that stopped the command line does not stop
+/// the trap command.
+pub fn run_trap(shell: &mut Shell, condition: TrapCondition) {
+    if let Some(action) = shell.traps.actions.get(&condition).cloned() {
+        run_action(shell, &action);
+    }
+}
+
+fn run_action(shell: &mut Shell, action: &str) {
+    if action.is_empty() {
+        return;
+    }
+    let status = shell.last_status;
+    let running = std::mem::replace(&mut shell.traps.running, true);
+    let interrupted = take_interrupt();
+    run_sourced(shell, action, &[]);
+    if interrupted {
+        interrupt();
+    }
+    shell.traps.running = running;
+    shell.last_status = status;
+}
+
+/// Run the commands of the trapped signals that arrived since the last call.
+pub fn run_pending_traps(shell: &mut Shell) {
+    for sig in take_pending() {
+        run_trap(shell, TrapCondition::Signal(sig));
+    }
+}
+
+/// After an and-or list failed: the ERR trap, unless the failure is a
+/// condition (`if`, `while`, `!`) or happens inside a function or another
+/// trap.
+pub fn run_err_trap(shell: &mut Shell) {
+    if shell.condition_depth == 0 && shell.locals.is_empty() && !shell.traps.running {
+        run_trap(shell, TrapCondition::Err);
+    }
+}
+
+/// The EXIT trap, once, as the shell is about to exit.
+pub fn run_exit_trap(shell: &mut Shell) {
+    // gone before it runs, so an `exit` in it does not run it again
+    if let Some(action) = shell.traps.actions.remove(&TrapCondition::Exit) {
+        run_action(shell, &action);
+    }
+}
+
+/// In a forked child: trap commands belong to the shell, so signals they
+/// caught get their default handling back. Ignored ones stay ignored.
+pub fn reset_traps(shell: &mut Shell) {
+    for (condition, action) in &shell.traps.actions {
+        if let TrapCondition::Signal(sig) = *condition
+            && !action.is_empty()
+        {
+            set_trapped(sig, false);
+            unsafe { libc::signal(sig, libc::SIG_DFL) };
+        }
+    }
+    shell.traps.actions.retain(|condition, action| matches!(condition, TrapCondition::Signal(_)) && action.is_empty());
+}
@@ -0,0 +1,168 @@
+use crate::history::store::*;
+
+/// Characters that end the `prefix` of a `!prefix` reference.
+fn ends_reference(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')' | '<' | '>' | '\'' | '"' | '`')
+}
+
+/// Read the reference after a `!` and look it up. Returns the entry's text,
+/// or the reference as typed when there is no such entry.
+fn resolve(rest: &str, history: &History) -> (Result<String, String>, usize) {
+    let entries = history.entries();
+    if rest.starts_with('!') {
+        let found = entries.last().map(|e| e.line.clone());
+        return (found.ok_or_else(|| "!!".to_string()), 1);
+    }
+
+    let digits_from = usize::from(rest.starts_with('-'));
+    let digits = rest[digits_from..].chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let len = digits_from + digits;
+        let reference = format!("!{}", &rest[..len]);
+        let n: usize = rest[digits_from..len].parse().unwrap_or(0);
+        let found = if digits_from == 1 {
+            // `!-n`: the n-th command back
+            entries.len().checked_sub(n).filter(|_| n > 0).map(|idx| entries[idx].line.clone())
+        } else {
+            history.get(n).map(String::from)
+        };
+        return (found.ok_or(reference), len);
+    }
+
+    let len = rest.find(ends_reference).unwrap_or(rest.len());
+    let prefix = &rest[..len];
+    let found = entries.iter().rev().find(|e| e.line.starts_with(prefix)).map(|e| e.line.clone());
+    (found.ok_or_else(|| format!("!{}", prefix)), len)
+}
+
+/// Replace history references in a line typed at the prompt: `!!` is the
+/// last command, `!n` command number `n`, `!-n` the `n`-th last and
+/// `!prefix` the last one starting with `prefix`.
+///
+/// A `!` in single quotes, after a backslash or `$`, or followed by a blank,
+/// `=`, `(` or the end of the line stays as it is. Returns None when nothing
+/// was replaced, and the reference that matched nothing as the error.
+pub fn expand_history(line: &str, history: &History) -> Result<Option<String>, String> {
+    let mut out = String::with_capacity(line.len());
+    let mut changed = false;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut prev = None;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '\\' if !in_single => {
+                out.push(c);
+                if let Some(next) = rest.chars().next() {
+                    out.push(next);
+                    rest = &rest[next.len_utf8()..];
+                }
+                prev = None;
+                continue;
+            }
+            '!' if !in_single && prev != Some('$') => {
+                let next = rest.chars().next();
+                let literal = match next {
+                    None => true,
+                    Some(n) => n.is_whitespace() || matches!(n, '=' | '(') || in_double && n == '"',
+                };
+                if !literal {
+                    let (found, len) = resolve(rest, history);
+                    out.push_str(&found?);
+                    rest = &rest[len..];
+                    changed = true;
+                    prev = None;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+    Ok(changed.then_some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> History {
+        let config = HistoryConfig {
+            file: None,
+            size: 100,
+            file_size: 100,
+            ignore_space: false,
+            ignore_dups: false,
+            erase_dups: false,
+        };
+        let mut history = History::default();
+        for line in ["echo one", "ls -l", "echo two"] {
+            history.add(line, 0, &config).unwrap();
+        }
+        history
+    }
+
+    fn expand(line: &str) -> Result<Option<String>, String> {
+        expand_history(line, &history())
+    }
+
+    #[test]
+    fn references() {
+        assert_eq!(expand("!!"), Ok(Some("echo two".to_string())));
+        assert_eq!(expand("sudo !!"), Ok(Some("sudo echo two".to_string())));
+        assert_eq!(expand("!1"), Ok(Some("echo one".to_string())));
+        assert_eq!(expand("!-2 -a"), Ok(Some("ls -l -a".to_string())));
+        assert_eq!(expand("!ec"), Ok(Some("echo two".to_string())));
+        assert_eq!(expand("!l"), Ok(Some("ls -l".to_string())));
+        assert_eq!(expand(r#"echo "!!""#), Ok(Some(r#"echo "echo two""#.to_string())));
+    }
+
+    #[test]
+    fn prefix_ends_at_blanks_and_operators() {
+        for (line, expanded) in [
+            ("!l;pwd", "ls -l;pwd"),
+            ("!l&&pwd", "ls -l&&pwd"),
+            ("!l|wc", "ls -l|wc"),
+            ("!l>out", "ls -l>out"),
+            ("!l<in", "ls -l<in"),
+            ("(!l)", "(ls -l)"),
+            ("!l'x'", "ls -l'x'"),
+            ("!l\"x\"", "ls -l\"x\""),
+            ("!l`x`", "ls -l`x`"),
+            ("!l &", "ls -l &"),
+        ] {
+            assert_eq!(expand(line), Ok(Some(expanded.to_string())), "{}", line);
+        }
+    }
+
+    #[test]
+    fn literal_bangs() {
+        for line in [
+            "echo '!!'",
+            "echo $!",
+            "[ a != b ]",
+            "echo !(x)",
+            "echo !",
+            "echo ! x",
+            r#"echo "hi!""#,
+            r"echo \!!",
+            "plain",
+        ] {
+            assert_eq!(expand(line), Ok(None), "{}", line);
+        }
+    }
+
+    #[test]
+    fn missing_references_are_errors() {
+        assert_eq!(expand("!nope"), Err("!nope".to_string()));
+        assert_eq!(expand("!99"), Err("!99".to_string()));
+        assert_eq!(expand("!-9"), Err("!-9".to_string()));
+        assert_eq!(expand("!-0"), Err("!-0".to_string()));
+        assert_eq!(expand_history("!!", &History::default()), Err("!!".to_string()));
+    }
+}
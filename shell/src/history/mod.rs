@@ -0,0 +1,2 @@
+pub mod bang;
+pub mod store;
\ No newline at end of file
@@ -0,0 +1,304 @@
+use crate::variables::shell::*;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Entries kept when `HISTSIZE` is not set.
+const DEFAULT_SIZE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub line: String,
+    // when it was typed, in seconds since the epoch
+    pub time: i64,
+}
+
+/// How history behaves, read from shell variables each time it is used so
+/// changes in `~/.0shellrc` or at the prompt apply right away.
+///
+/// - `HISTFILE`: where history is saved, `~/.0shell_history` by default
+/// - `HISTSIZE`: how many entries are kept in memory
+/// - `HISTFILESIZE`: how many entries the file keeps, `HISTSIZE` by default
+/// - `HISTCONTROL`: `:`-separated `ignorespace`, `ignoredups`, `ignoreboth`
+///   and `erasedups`; `ignoredups` when unset
+pub struct HistoryConfig {
+    pub file: Option<PathBuf>,
+    pub size: usize,
+    pub file_size: usize,
+    pub ignore_space: bool,
+    pub ignore_dups: bool,
+    pub erase_dups: bool,
+}
+
+impl HistoryConfig {
+    pub fn from_shell(shell: &Shell) -> Self {
+        let number = |name: &str| shell.get_var(name).and_then(|v| v.trim().parse::<usize>().ok());
+        let size = number("HISTSIZE").unwrap_or(DEFAULT_SIZE);
+        let file = match shell.get_var("HISTFILE") {
+            Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            Some(_) => None,
+            None => shell.get_var("HOME").map(|home| PathBuf::from(home).join(".0shell_history")),
+        };
+        let control = shell.get_var("HISTCONTROL").unwrap_or("ignoredups");
+        let has = |word: &str| control.split(':').any(|c| c == word);
+        HistoryConfig {
+            file,
+            size,
+            file_size: number("HISTFILESIZE").unwrap_or(size),
+            ignore_space: has("ignorespace") || has("ignoreboth"),
+            ignore_dups: has("ignoredups") || has("ignoreboth"),
+            erase_dups: has("erasedups"),
+        }
+    }
+}
+
+/// Commands typed at the prompt, oldest first.
+///
+/// Each entry is appended to the history file as soon as it is added, after
+/// a `#<seconds>` line that marks when it was typed. The marks also keep
+/// multi-line commands together when the file is read back.
+#[derive(Debug)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    // the number `history` shows for the first entry; it grows as old
+    // entries are dropped so the others keep their numbers
+    base: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History { entries: Vec::new(), base: 1 }
+    }
+}
+
+/// Split the text of a history file into entries. Without timestamps every
+/// line is an entry of its own.
+fn parse_file(text: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    // whether the entry being read started with a timestamp and so may
+    // go on over several lines
+    let mut stamped = false;
+    for line in text.lines() {
+        if let Some(time) = line.strip_prefix('#').and_then(|t| t.parse().ok()) {
+            entries.push(HistoryEntry { line: String::new(), time });
+            stamped = true;
+            continue;
+        }
+        match entries.last_mut() {
+            Some(entry) if stamped && !entry.line.is_empty() => {
+                entry.line.push('\n');
+                entry.line.push_str(line);
+            }
+            Some(entry) if stamped => entry.line.push_str(line),
+            _ => entries.push(HistoryEntry { line: line.to_string(), time: 0 }),
+        }
+    }
+    entries.retain(|e| !e.line.is_empty());
+    entries
+}
+
+fn format_entries(entries: &[HistoryEntry]) -> String {
+    let mut text = String::new();
+    for entry in entries {
+        text.push_str(&format!("#{}\n{}\n", entry.time, entry.line));
+    }
+    text
+}
+
+impl History {
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Every entry's text, oldest first.
+    pub fn lines(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.line.clone()).collect()
+    }
+
+    /// The entry `history` shows as number `n`.
+    pub fn get(&self, n: usize) -> Option<&str> {
+        let idx = n.checked_sub(self.base)?;
+        self.entries.get(idx).map(|e| e.line.as_str())
+    }
+
+    /// Drop the oldest entries past `size`.
+    fn trim(&mut self, size: usize) {
+        if self.entries.len() > size {
+            let extra = self.entries.len() - size;
+            self.entries.drain(..extra);
+            self.base += extra;
+        }
+    }
+
+    /// Read the history file, and shorten it if it grew past `HISTFILESIZE`.
+    pub fn load(&mut self, config: &HistoryConfig) -> io::Result<()> {
+        let Some(path) = &config.file else {
+            return Ok(());
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let mut entries = parse_file(&text);
+        if config.erase_dups {
+            // keep the most recent copy of each line
+            let mut seen = HashSet::new();
+            let mut kept: Vec<HistoryEntry> = entries.into_iter().rev().filter(|e| seen.insert(e.line.clone())).collect();
+            kept.reverse();
+            entries = kept;
+        }
+        if entries.len() > config.file_size {
+            entries.drain(..entries.len() - config.file_size);
+            fs::write(path, format_entries(&entries))?;
+        }
+        self.entries = entries;
+        self.base = 1;
+        self.trim(config.size);
+        Ok(())
+    }
+
+    /// Add a command and append it to the history file, unless
+    /// `HISTCONTROL` says to leave it out.
+    pub fn add(&mut self, line: &str, time: i64, config: &HistoryConfig) -> io::Result<()> {
+        if config.size == 0 || config.ignore_space && line.starts_with(' ') {
+            return Ok(());
+        }
+        if config.ignore_dups && self.entries.last().is_some_and(|e| e.line == line) {
+            return Ok(());
+        }
+        if config.erase_dups {
+            self.entries.retain(|e| e.line != line);
+        }
+        let entry = HistoryEntry { line: line.to_string(), time };
+        self.entries.push(entry.clone());
+        self.trim(config.size);
+
+        match &config.file {
+            Some(path) if config.file_size > 0 => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(format_entries(&[entry]).as_bytes())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove entry number `n`; the ones after it move down by one.
+    pub fn delete(&mut self, n: usize) -> bool {
+        match n.checked_sub(self.base).filter(|&idx| idx < self.entries.len()) {
+            Some(idx) => {
+                self.entries.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.base = 1;
+    }
+
+    /// Replace the history file with the entries in memory, after `-c` or
+    /// `-d` changed them.
+    pub fn save(&self, config: &HistoryConfig) -> io::Result<()> {
+        match &config.file {
+            Some(path) => fs::write(path, format_entries(&self.entries)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::builtin::*;
+
+    fn config(file: Option<PathBuf>, size: usize) -> HistoryConfig {
+        HistoryConfig { file, size, file_size: size, ignore_space: false, ignore_dups: false, erase_dups: false }
+    }
+
+    fn history_of(lines: &[&str], config: &HistoryConfig) -> History {
+        let mut history = History::default();
+        for (time, line) in lines.iter().enumerate() {
+            history.add(line, time as i64, config).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn parse_file_keeps_stamped_entries_together() {
+        let entries = parse_file("#100\nfor i in a\ndo echo $i\ndone\n#101\nls\n#102\n");
+        let lines: Vec<(&str, i64)> = entries.iter().map(|e| (e.line.as_str(), e.time)).collect();
+        assert_eq!(lines, vec![("for i in a\ndo echo $i\ndone", 100), ("ls", 101)]);
+    }
+
+    #[test]
+    fn parse_file_without_stamps_is_one_entry_per_line() {
+        let entries = parse_file("ls\npwd\n\necho\n");
+        let lines: Vec<&str> = entries.iter().map(|e| e.line.as_str()).collect();
+        assert_eq!(lines, vec!["ls", "pwd", "echo"]);
+    }
+
+    #[test]
+    fn multi_line_entries_survive_a_round_trip() {
+        let path = scratch("history-round-trip");
+        let config = config(Some(path.clone()), 100);
+        let written = history_of(&["ls", "if true\nthen echo '#1'\nfi", "echo \"a\nb\""], &config);
+        let mut read = History::default();
+        read.load(&config).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.lines(), written.lines());
+        let times: Vec<i64> = read.entries().iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn ignoredups_skips_only_repeats_in_a_row() {
+        let config = HistoryConfig { ignore_dups: true, ..config(None, 100) };
+        assert_eq!(history_of(&["ls", "ls", "pwd", "ls"], &config).lines(), vec!["ls", "pwd", "ls"]);
+    }
+
+    #[test]
+    fn erasedups_keeps_the_latest_copy() {
+        let config = HistoryConfig { erase_dups: true, ..config(None, 100) };
+        assert_eq!(history_of(&["ls", "pwd", "ls", "cd"], &config).lines(), vec!["pwd", "ls", "cd"]);
+    }
+
+    #[test]
+    fn ignorespace_skips_lines_starting_with_a_blank() {
+        let config = HistoryConfig { ignore_space: true, ..config(None, 100) };
+        assert_eq!(history_of(&["ls", " secret", "pwd"], &config).lines(), vec!["ls", "pwd"]);
+    }
+
+    #[test]
+    fn histsize_drops_the_oldest_and_keeps_numbers() {
+        let history = history_of(&["a", "b", "c", "d"], &config(None, 2));
+        assert_eq!(history.lines(), vec!["c", "d"]);
+        assert_eq!(history.base(), 3);
+        assert_eq!(history.get(3), Some("c"));
+        assert_eq!(history.get(1), None);
+        assert!(history_of(&["a"], &config(None, 0)).entries().is_empty());
+    }
+
+    #[test]
+    fn load_trims_the_file_and_erases_dups() {
+        let path = scratch("history-load");
+        history_of(&["a", "b", "a", "c", "d"], &config(Some(path.clone()), 100));
+        let config = HistoryConfig { file_size: 3, erase_dups: true, ..config(Some(path.clone()), 2) };
+        let mut history = History::default();
+        history.load(&config).unwrap();
+        let rewritten = parse_file(&fs::read_to_string(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        // b a c d once the older `a` is gone, then the file keeps 3 and memory 2
+        let file_lines: Vec<&str> = rewritten.iter().map(|e| e.line.as_str()).collect();
+        assert_eq!(file_lines, vec!["a", "c", "d"]);
+        assert_eq!(history.lines(), vec!["c", "d"]);
+        assert_eq!(history.base(), 2);
+    }
+}
@@ -1,146 +1,103 @@
 mod commands;
+mod editor;
+mod exec;
+mod history;
 mod parsing;
 mod variables;
 
-use commands::clear::*;
-use commands::cp::*;
-use commands::ls::*;
-use commands::cat::*;
-use commands::cd::*;
-use commands::mkdir::*;
-use commands::mv::*;
-use commands::rm::*;
+use exec::script::*;
+use exec::signal::*;
+use exec::trap::*;
+use history::store::*;
 use parsing::split_save::*;
 use std::env;
-use std::io::Write;
-
-fn main() {
-    let stdin = std::io::stdin();
-    let mut stdout = std::io::stdout();
-    let mut var;
-
-    loop {
-        if let Ok(path) = env::current_dir() {
-            print!("{}$ ", path.display());
-        } else {
-            print!("$ ");
+use std::fs;
+use std::io;
+use std::path::Path;
+use variables::shell::*;
+
+/// Pick the command source from the arguments: `-c string [name [args...]]`,
+/// `script [args...]`, or stdin when there are none.
+fn input_from_args(shell: &mut Shell, args: &[String]) -> Input {
+    match args.first().map(String::as_str) {
+        None => {
+            shell.interactive = unsafe { libc::isatty(0) } == 1;
+            Input::stdin(shell.interactive)
         }
-
-        stdout.flush().unwrap();
-
-        let mut input = String::new();
-        match stdin.read_line(&mut input) {
-            Ok(0) => {
-                println!("\nexit");
-                break;
-            }
-            Ok(_) => {}
-            Err(_) => {
-                break;
+        Some("-c") => {
+            let Some(command) = args.get(1) else {
+                eprintln!("0-shell: -c: option requires an argument");
+                shell.exit(2);
+            };
+            if let Some(name) = args.get(2) {
+                shell.arg0 = name.clone();
             }
+            shell.positional = args.get(3..).unwrap_or_default().to_vec();
+            Input::text(command)
         }
-
-        let command = input.trim().to_string();
-        if command.is_empty() {
-            continue;
-        }
-
-        var = split_save(command.clone());
-
-        match var.command.as_str() {
-            "exit" => break,
-
-            "echo" => {
-                if !var.args.is_empty() {
-                    println!("{}", var.args.join(" "));
-                } else {
-                    println!("\n");
-                }
-            }
-
-            "clear" => clearaw(),
-
-            "pwd" => match env::current_dir() {
-                Ok(path) => println!("{}", path.display()),
-                Err(e) => eprintln!("Error getting current directory: {}", e),
-            },
-//a
-            "cat" => {
-                 if !var.flags.is_empty(){
-                eprintln!("cat: -{} is not supported", var.flags.join(""));
-                    continue;
-                }
-                let args: Vec<&str> = var.args.iter().map(|s| s.as_str()).collect();
-
-                // Handle no arguments: read from stdin
-                if args.is_empty() {
-                    if let Err(e) = catfile(&[]) {
-                        eprintln!("cat: {}", e);
-                    }
-                    continue;
-                }
-
-                // Handle each file argument
-                for &file in &args {
-                    if let Err(_) = catfile(&[file]) {
-                        eprintln!("cat: {}: No such file or directory", file);
-                    }
-                }
-            }
-
-            "cd" => cdd(&var.args),
-
-            "ls" => lss(&var.flags, &var.args),
-            "mkdir" => {
-                 if !var.flags.is_empty(){
-                eprintln!("mkdir: -{} is not supported", var.flags.join(""));
-                    continue;
-                }
-                if var.args.is_empty() {
-                    eprintln!("mkdir: missing operand");
-                    continue;
-                }
-                if let Err(e) = mkdirr(&var.args) {
-                    eprintln!("mkdir: {}", e);
+        Some(path) => {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) => {
+                    let msg = match e.kind() {
+                        io::ErrorKind::NotFound => "No such file or directory".to_string(),
+                        _ => e.to_string(),
+                    };
+                    eprintln!("0-shell: {}: {}", path, msg);
+                    shell.exit(127);
                 }
-            }
-
-            "cp" => {
-                 if !var.flags.is_empty(){
-                eprintln!("cp: -{} is not supported", var.flags.join(""));
-                    continue;
-                }
-                if var.args.len() < 2 {
-                    eprintln!("cp: missing file operand");
-                    continue;
-                }
-                if let Err(e) = cpp(&var.args) {
-                    eprintln!("cp error: {}", e);
-                }
-            }
+            };
+            shell.arg0 = path.to_string();
+            shell.positional = args[1..].to_vec();
+            Input::text(&text)
+        }
+    }
+}
 
-            "mv" => {
-                if !var.flags.is_empty(){
-                eprintln!("mv: -{} is not supported", var.flags.join(""));
-                    continue;
-                }
-                if var.args.len() < 2 {
-                    eprintln!("mv: missing file operand");
-                    continue;
-                }
-                if let Err(e) = mvv(&var.args) {
-                    eprintln!("mv error: {}", e);
-                }
-            }
+/// Run `~/.0shellrc` if there is one.
+fn load_rc(shell: &mut Shell) {
+    let Some(home) = shell.get_var("HOME") else {
+        return;
+    };
+    let path = Path::new(home).join(".0shellrc");
+    // a missing rc file is fine, an unreadable one is worth a word
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            run_sourced(shell, &text, &[]);
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!("0-shell: {}: {}", path.display(), e),
+    }
+}
 
-            "rm" => {
-                if let Err(e) = rm(&var.flags, &var.args) {
-                    eprintln!("rm: {}", e);
-                }
-            }
+fn main() {
+    let mut shell = Shell::new();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let norc = args.first().is_some_and(|a| a == "--norc");
+    if norc {
+        args.remove(0);
+    }
+    let mut input = input_from_args(&mut shell, &args);
+    if shell.interactive {
+        shell.jobs.enable();
+        catch_interrupts();
+    }
 
-            _ => println!("command not found: {}", var.command),
+    if shell.interactive && !norc {
+        load_rc(&mut shell);
+    }
+    // after the rc file, which may set HISTFILE and HISTSIZE
+    if shell.interactive {
+        let config = HistoryConfig::from_shell(&shell);
+        if let Err(e) = shell.history.load(&config) {
+            eprintln!("0-shell: {}: {}", config.file.unwrap_or_default().display(), e);
         }
     }
-}
+    run_input(&mut shell, &mut input);
+    if shell.interactive {
+        println!("\nexit");
+    }
 
+    run_exit_trap(&mut shell);
+    shell.exit(shell.last_status);
+}
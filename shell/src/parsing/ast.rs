@@ -0,0 +1,179 @@
+use std::rc::Rc;
+
+/// Byte range of a node in the source it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A word as typed, quotes included. It is only expanded when the command
+/// it belongs to runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedirectKind {
+    // [n]<file
+    Input,
+    // [n]>file
+    Output,
+    // [n]>>file
+    Append,
+    // &>file, stdout and stderr to the same file
+    Both,
+    // &>>file
+    BothAppend,
+    // [n]>&m
+    Dup(i32),
+    // [n]>&-
+    Close,
+}
+
+impl RedirectKind {
+    pub fn needs_target(&self) -> bool {
+        !matches!(self, RedirectKind::Dup(_) | RedirectKind::Close)
+    }
+
+    pub fn operator(&self) -> &'static str {
+        match self {
+            RedirectKind::Input => "<",
+            RedirectKind::Output => ">",
+            RedirectKind::Append => ">>",
+            RedirectKind::Both => "&>",
+            RedirectKind::BothAppend => "&>>",
+            RedirectKind::Dup(_) | RedirectKind::Close => ">&",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub fd: i32,
+    pub kind: RedirectKind,
+    // None for `>&m` and `>&-`
+    pub target: Option<Word>,
+    pub span: Span,
+}
+
+/// `NAME=value` written before the command name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Word,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleCommand {
+    pub assignments: Vec<Assignment>,
+    // the command name and its arguments, in order
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompoundCommand {
+    // { list; }
+    Group(List),
+    // ( list )
+    Subshell(List),
+    // if list; then list; [elif list; then list;]... [else list;] fi
+    If {
+        branches: Vec<(List, List)>,
+        else_body: Option<List>,
+    },
+    // while list; do list; done, or until when `until` is set
+    While {
+        cond: List,
+        body: List,
+        until: bool,
+    },
+    // for name [in word...]; do list; done
+    For {
+        name: String,
+        // None without `in`: loop over the positional parameters
+        words: Option<Vec<Word>>,
+        body: List,
+    },
+    // case word in [(]pattern[|pattern]...) list;; ... esac
+    Case {
+        word: Word,
+        arms: Vec<CaseArm>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseArm {
+    pub patterns: Vec<Word>,
+    pub body: List,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    Compound {
+        body: CompoundCommand,
+        redirects: Vec<Redirect>,
+        span: Span,
+    },
+    // name() compound-command
+    FunctionDef {
+        name: String,
+        // shared with the function table, which keeps it after the line is gone
+        body: Rc<Command>,
+        span: Span,
+    },
+}
+
+/// Commands joined by `|`, optionally negated with `!`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<Command>,
+    pub span: Span,
+    // the source text, for `jobs`
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AndOr {
+    // `&&`, run only if the previous status is 0
+    And,
+    // `||`, run only if the previous status is not 0
+    Or,
+}
+
+/// Pipelines joined by `&&` and `||`, evaluated left to right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AndOrList {
+    pub first: Pipeline,
+    pub rest: Vec<(AndOr, Pipeline)>,
+    // ended by `&`: run it in the background as a job
+    pub background: bool,
+}
+
+impl AndOrList {
+    /// How the list is shown in `jobs`.
+    pub fn text(&self) -> String {
+        let mut text = self.first.text.clone();
+        for (op, pipeline) in &self.rest {
+            text.push_str(match op {
+                AndOr::And => " && ",
+                AndOr::Or => " || ",
+            });
+            text.push_str(&pipeline.text);
+        }
+        text
+    }
+}
+
+/// And-or lists separated by `;`, `&` or newlines.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct List {
+    pub items: Vec<AndOrList>,
+}
@@ -0,0 +1,483 @@
+use crate::exec::substitution::*;
+use crate::exec::redirect::*;
+use crate::parsing::ast::*;
+use crate::parsing::glob::*;
+use crate::parsing::lexer::*;
+use crate::variables::shell::*;
+use std::env;
+use std::iter::Peekable;
+use std::process;
+use std::str::Chars;
+use users::get_user_by_name;
+use users::os::unix::UserExt;
+
+const DEFAULT_IFS: &str = " \t\n";
+
+/// A field after expansion. `pattern` is set when the field has unquoted
+/// glob characters; quoted characters in it are escaped with a backslash.
+struct Field {
+    text: String,
+    pattern: Option<String>,
+}
+
+/// Fields produced while expanding one word.
+struct Fields {
+    fields: Vec<Field>,
+    current: String,
+    // `current` as a glob pattern
+    pattern: String,
+    glob: bool,
+    // the current field exists even if still empty (`""`, `a$EMPTY`)
+    started: bool,
+    ifs: String,
+}
+
+impl Fields {
+    fn new(shell: &Shell) -> Self {
+        Fields {
+            fields: Vec::new(),
+            current: String::new(),
+            pattern: String::new(),
+            glob: false,
+            started: false,
+            ifs: shell.get_var("IFS").unwrap_or(DEFAULT_IFS).to_string(),
+        }
+    }
+
+    /// Add a quoted character: it never acts as a glob character.
+    fn push(&mut self, c: char) {
+        self.current.push(c);
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            self.pattern.push('\\');
+        }
+        self.pattern.push(c);
+        self.started = true;
+    }
+
+    fn push_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.push(c);
+        }
+        self.started = true;
+    }
+
+    /// Add an unquoted character, which may be a glob character.
+    fn push_unquoted(&mut self, c: char) {
+        self.current.push(c);
+        self.pattern.push(c);
+        if matches!(c, '*' | '?' | '[') {
+            self.glob = true;
+        }
+        self.started = true;
+    }
+
+    fn end_field(&mut self) {
+        let pattern = std::mem::take(&mut self.pattern);
+        self.fields.push(Field {
+            text: std::mem::take(&mut self.current),
+            pattern: if self.glob { Some(pattern) } else { None },
+        });
+        self.glob = false;
+        self.started = false;
+    }
+
+    /// Add the result of an unquoted expansion, splitting it on IFS.
+    fn push_split(&mut self, s: &str) {
+        for c in s.chars() {
+            if !self.ifs.contains(c) {
+                self.push_unquoted(c);
+            } else if !c.is_whitespace() || self.started {
+                // IFS whitespace only ends a field, other IFS characters
+                // delimit one even if it is empty
+                self.end_field();
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Field> {
+        if self.started {
+            self.end_field();
+        }
+        self.fields
+    }
+}
+
+/// Expand `$` parameters in a word, split the unquoted results into fields,
+/// expand glob patterns and remove quotes.
+///
+/// Single quotes keep everything literal; inside double quotes `$` is still
+/// expanded and a backslash only escapes `$`, `` ` ``, `"`, `\` and newline.
+pub fn expand_word(shell: &mut Shell, word: &str) -> Result<Vec<String>, String> {
+    let mut fields = Fields::new(shell);
+    expand_into(shell, word, false, &mut fields)?;
+
+    let mut out = Vec::new();
+    for field in fields.finish() {
+        let matches = field.pattern.as_deref().map(glob).unwrap_or_default();
+        // like sh, a pattern that matches nothing is left as it is
+        if matches.is_empty() {
+            out.push(field.text);
+        } else {
+            out.extend(matches);
+        }
+    }
+    Ok(out)
+}
+
+/// Expand a word into exactly one string, without field splitting
+/// (assignment values, `${VAR:-word}` defaults).
+pub fn expand_string(shell: &mut Shell, word: &str) -> Result<String, String> {
+    let mut fields = Fields::new(shell);
+    fields.ifs.clear();
+    expand_into(shell, word, false, &mut fields)?;
+    Ok(fields.finish().into_iter().map(|f| f.text).collect())
+}
+
+/// Expand a word into a glob pattern for `case`: expansions happen as usual,
+/// but quoted characters match only themselves.
+pub fn expand_pattern(shell: &mut Shell, word: &str) -> Result<String, String> {
+    let mut fields = Fields::new(shell);
+    fields.ifs.clear();
+    fields.started = true;
+    expand_into(shell, word, false, &mut fields)?;
+    fields.end_field();
+    Ok(fields.fields.into_iter().map(|f| f.pattern.unwrap_or_else(|| escape_pattern(&f.text))).collect())
+}
+
+/// Quote every glob character of `s` with a backslash.
+fn escape_pattern(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Expand the value of a `NAME=value` assignment: like `expand_string`, but
+/// a tilde is also expanded after each `:` (`PATH=~/bin:~/.local/bin`).
+pub fn expand_assignment(shell: &mut Shell, value: &str) -> Result<String, String> {
+    let mut fields = Fields::new(shell);
+    fields.ifs.clear();
+    expand_into(shell, value, true, &mut fields)?;
+    Ok(fields.finish().into_iter().map(|f| f.text).collect())
+}
+
+/// Resolve a tilde prefix (what follows `~` up to the first `/`):
+/// `~` is $HOME, `~+` $PWD, `~-` $OLDPWD and `~user` that user's home.
+pub fn tilde_value(shell: &Shell, prefix: &str) -> Option<String> {
+    match prefix {
+        "" => shell.get_var("HOME").map(|h| h.to_string()),
+        "+" => match shell.get_var("PWD") {
+            Some(pwd) => Some(pwd.to_string()),
+            None => env::current_dir().ok().map(|p| p.display().to_string()),
+        },
+        "-" => shell.get_var("OLDPWD").map(|p| p.to_string()),
+        user => get_user_by_name(user).map(|u| u.home_dir().display().to_string()),
+    }
+}
+
+fn expand_into(shell: &mut Shell, word: &str, assignment: bool, fields: &mut Fields) -> Result<(), String> {
+    let mut chars = word.chars().peekable();
+    let mut in_single = false;
+    let mut in_double = false;
+    // a tilde is only special at the start of the word (or after `:` in
+    // an assignment) and when unquoted
+    let mut tilde_ok = true;
+
+    while let Some(c) = chars.next() {
+        let at_start = std::mem::replace(&mut tilde_ok, false);
+        match c {
+            '~' if at_start => {
+                let prefix: String = chars
+                    .clone()
+                    .take_while(|&c| c != '/' && !(assignment && c == ':'))
+                    .collect();
+                let quoted = prefix.contains(['\'', '"', '\\', '$', '`']);
+                match tilde_value(shell, &prefix) {
+                    Some(value) if !quoted => {
+                        for _ in prefix.chars() {
+                            chars.next();
+                        }
+                        // the result is never split or globbed
+                        fields.push_str(&value);
+                    }
+                    _ => fields.push_unquoted(c),
+                }
+            }
+            ':' if assignment && !in_single && !in_double => {
+                tilde_ok = true;
+                fields.push_unquoted(c);
+            }
+            // an opening quote makes a field even if nothing follows (`""`)
+            '\'' if !in_double => {
+                in_single = !in_single;
+                fields.started |= in_single;
+            }
+            '"' if !in_single => {
+                in_double = !in_double;
+                fields.started |= in_double;
+            }
+            '\\' if !in_single => match chars.next() {
+                Some(next) if !in_double || matches!(next, '$' | '`' | '"' | '\\' | '\n') => {
+                    fields.push(next)
+                }
+                Some(next) => {
+                    fields.push('\\');
+                    fields.push(next);
+                }
+                None => fields.push('\\'),
+            },
+            '`' if !in_single => {
+                let inner = read_backquoted(&mut chars)
+                    .ok_or("unexpected EOF while looking for matching ``'")?;
+                // inside backquotes a backslash only quotes `$`, `` ` `` and `\`
+                let mut source = String::with_capacity(inner.len());
+                let mut inner_chars = inner.chars().peekable();
+                while let Some(c) = inner_chars.next() {
+                    match (c, inner_chars.peek()) {
+                        ('\\', Some(&next @ ('$' | '`' | '\\'))) => {
+                            inner_chars.next();
+                            source.push(next);
+                        }
+                        _ => source.push(c),
+                    }
+                }
+                let value = command_output(shell, &source);
+                if in_double {
+                    fields.push_str(&value);
+                } else {
+                    fields.push_split(&value);
+                }
+            }
+            // "$@" is one field per positional parameter, and none at all
+            // when there are none
+            '$' if !in_single && chars.peek() == Some(&'@') => {
+                chars.next();
+                if in_double && shell.positional.is_empty() && fields.current.is_empty() {
+                    fields.started = false;
+                }
+                for (idx, param) in shell.positional.iter().enumerate() {
+                    if idx > 0 {
+                        fields.end_field();
+                    }
+                    if in_double {
+                        fields.push_str(param);
+                    } else {
+                        fields.push_split(param);
+                    }
+                }
+            }
+            '$' if !in_single => {
+                let value = expand_parameter(shell, &mut chars)?;
+                match value {
+                    Some(v) if in_double => fields.push_str(&v),
+                    Some(v) => fields.push_split(&v),
+                    None => fields.push('$'),
+                }
+            }
+            _ if in_single || in_double => fields.push(c),
+            _ => fields.push_unquoted(c),
+        }
+    }
+
+    Ok(())
+}
+
+fn read_name(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c != '_' && !c.is_ascii_alphanumeric() {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    name
+}
+
+/// Read a parameter name: a variable name, a number for a positional
+/// parameter (one digit unless braced) or a special parameter.
+fn read_param_name(chars: &mut Peekable<Chars>, braced: bool) -> String {
+    match chars.peek() {
+        Some(&c @ ('?' | '$' | '!' | '#' | '*' | '@')) => {
+            chars.next();
+            c.to_string()
+        }
+        Some(c) if c.is_ascii_digit() && !braced => chars.next().map(String::from).unwrap_or_default(),
+        Some(c) if c.is_ascii_digit() => {
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                digits.push(d);
+            }
+            digits
+        }
+        _ => read_name(chars),
+    }
+}
+
+/// Value of a parameter by name: special parameters, positional parameters
+/// and shell variables. None when it is unset.
+fn param_value(shell: &Shell, name: &str) -> Option<String> {
+    match name {
+        "?" => Some(shell.last_status.to_string()),
+        "$" => Some(process::id().to_string()),
+        // the last job started with `&`
+        "!" => shell.last_bg_pid.map(|pid| pid.to_string()),
+        "#" => Some(shell.positional.len().to_string()),
+        "0" => Some(shell.arg0.clone()),
+        // joined with the first IFS character, a space by default
+        "*" | "@" => {
+            let sep = match shell.get_var("IFS") {
+                Some(ifs) => ifs.chars().next().map(String::from).unwrap_or_default(),
+                None => " ".to_string(),
+            };
+            Some(shell.positional.join(&sep))
+        }
+        _ if name.chars().all(|c| c.is_ascii_digit()) => {
+            let n: usize = name.parse().ok()?;
+            shell.positional.get(n.checked_sub(1)?).cloned()
+        }
+        _ => shell.get_var(name).map(|v| v.to_string()),
+    }
+}
+
+/// Expand the parameter following a `$` that was just consumed.
+/// Returns None when what follows is not a parameter, so the `$` stays literal.
+fn expand_parameter(shell: &mut Shell, chars: &mut Peekable<Chars>) -> Result<Option<String>, String> {
+    let value = match chars.peek() {
+        Some('{') => {
+            chars.next();
+            expand_braced(shell, chars)?
+        }
+        Some('(') => {
+            chars.next();
+            let source = read_nested(chars, '(', ')')
+                .ok_or("unexpected EOF while looking for matching `)'")?;
+            command_output(shell, &source)
+        }
+        Some(&c) if c == '_' || c.is_ascii_alphanumeric() || "?$!#*@".contains(c) => {
+            let name = read_param_name(chars, false);
+            param_value(shell, &name).unwrap_or_default()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+/// Expand `${...}` once the opening brace was consumed: `${NAME}`, `${#NAME}`
+/// and `${NAME<op>word}` with op one of `-`, `=`, `+`, `?`, optionally
+/// preceded by `:` to also treat an empty value as unset.
+fn expand_braced(shell: &mut Shell, chars: &mut Peekable<Chars>) -> Result<String, String> {
+    // read up to the matching brace
+    let mut body = String::new();
+    let mut depth = 0;
+    loop {
+        match chars.next() {
+            Some('}') if depth == 0 => break,
+            Some(c) => {
+                if c == '{' {
+                    depth += 1;
+                } else if c == '}' {
+                    depth -= 1;
+                }
+                body.push(c);
+            }
+            None => return Err(format!("${{{}: bad substitution", body)),
+        }
+    }
+    let bad = || format!("${{{}}}: bad substitution", body);
+
+    let mut rest = body.chars().peekable();
+    if body.starts_with('#') && body.len() > 1 {
+        rest.next();
+        let name = read_param_name(&mut rest, true);
+        if name.is_empty() || rest.peek().is_some() {
+            return Err(bad());
+        }
+        return Ok(param_value(shell, &name).unwrap_or_default().chars().count().to_string());
+    }
+
+    let name = read_param_name(&mut rest, true);
+    if name.is_empty() {
+        return Err(bad());
+    }
+    let value = param_value(shell, &name);
+
+    let colon = rest.peek() == Some(&':');
+    if colon {
+        rest.next();
+    }
+    let op = match rest.next() {
+        None if !colon => return Ok(value.unwrap_or_default()),
+        Some(op @ ('-' | '=' | '+' | '?')) => op,
+        _ => return Err(bad()),
+    };
+    let word: String = rest.collect();
+    let is_set = match &value {
+        Some(v) => !(colon && v.is_empty()),
+        None => false,
+    };
+
+    match op {
+        '-' if !is_set => expand_string(shell, &word),
+        '=' if !is_set => {
+            if !is_valid_name(&name) {
+                return Err(format!("${}: cannot assign in this way", name));
+            }
+            let new = expand_string(shell, &word)?;
+            shell.set_var(&name, &new);
+            Ok(new)
+        }
+        '+' if is_set => expand_string(shell, &word),
+        '+' => Ok(String::new()),
+        '?' if !is_set => {
+            let msg = expand_string(shell, &word)?;
+            let msg = if msg.is_empty() { "parameter null or not set".to_string() } else { msg };
+            Err(format!("{}: {}", name, msg))
+        }
+        _ => Ok(value.unwrap_or_default()),
+    }
+}
+
+/// A simple command after expansion, ready to run.
+pub struct Expanded {
+    pub argv: Vec<String>,
+    pub assignments: Vec<(String, String)>,
+    pub redirects: Vec<Redirection>,
+}
+
+/// Expand redirection targets. Each must expand to exactly one field.
+pub fn expand_redirects(shell: &mut Shell, redirects: &[Redirect]) -> Result<Vec<Redirection>, String> {
+    let mut expanded = Vec::with_capacity(redirects.len());
+    for r in redirects {
+        let mut target = String::new();
+        if let Some(word) = &r.target {
+            let mut fields = expand_word(shell, &word.text)?;
+            if fields.len() != 1 {
+                return Err(format!("{}: ambiguous redirect", word.text));
+            }
+            target = fields.pop().unwrap_or_default();
+        }
+        expanded.push(Redirection { fd: r.fd, kind: r.kind, target });
+    }
+    Ok(expanded)
+}
+
+/// Expand the assignments, words and redirection targets of a command.
+pub fn expand_command(shell: &mut Shell, cmd: &SimpleCommand) -> Result<Expanded, String> {
+    let mut assignments = Vec::with_capacity(cmd.assignments.len());
+    for a in &cmd.assignments {
+        assignments.push((a.name.clone(), expand_assignment(shell, &a.value.text)?));
+    }
+
+    let mut argv = Vec::new();
+    for word in &cmd.words {
+        argv.extend(expand_word(shell, &word.text)?);
+    }
+
+    let redirects = expand_redirects(shell, &cmd.redirects)?;
+    Ok(Expanded { argv, assignments, redirects })
+}
@@ -0,0 +1,327 @@
+use crate::commands::ls::ls_cmp;
+use std::fs;
+use std::path::Path;
+
+/// Whether a pattern has unescaped `*`, `?` or `[` in it.
+pub fn has_meta(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Remove the backslashes a pattern uses to quote its metacharacters.
+pub fn unescape(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Match a single file name against a pattern component.
+pub fn fnmatch(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    match_from(&p, &n)
+}
+
+fn match_from(p: &[char], n: &[char]) -> bool {
+    let (mut pi, mut ni) = (0, 0);
+    // where to resume after the last `*`: (pattern index, name index)
+    let mut star: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        if pi < p.len() {
+            match p[pi] {
+                '*' => {
+                    star = Some((pi + 1, ni));
+                    pi += 1;
+                    continue;
+                }
+                '?' => {
+                    pi += 1;
+                    ni += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, len)) = match_class(&p[pi..], n[ni]) {
+                        if matched {
+                            pi += len;
+                            ni += 1;
+                            continue;
+                        }
+                    } else if n[ni] == '[' {
+                        // no closing bracket: a plain `[`
+                        pi += 1;
+                        ni += 1;
+                        continue;
+                    }
+                }
+                '\\' if pi + 1 < p.len() => {
+                    if p[pi + 1] == n[ni] {
+                        pi += 2;
+                        ni += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == n[ni] {
+                        pi += 1;
+                        ni += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        // mismatch: let the last `*` eat one more character
+        match star {
+            Some((sp, sn)) => {
+                pi = sp;
+                ni = sn + 1;
+                star = Some((sp, sn + 1));
+            }
+            None => return false,
+        }
+    }
+
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Match `c` against the bracket expression at the start of `p`.
+/// Returns whether it matched and the length of the expression, or None
+/// when the bracket is never closed.
+fn match_class(p: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = matches!(p.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let mut lo = *p.get(i)?;
+        if lo == ']' && !first {
+            break;
+        }
+        first = false;
+        if lo == '\\' {
+            i += 1;
+            lo = *p.get(i)?;
+        }
+        i += 1;
+
+        if p.get(i) == Some(&'-') && p.get(i + 1).is_some_and(|&h| h != ']') {
+            let mut hi = p[i + 1];
+            i += 2;
+            if hi == '\\' {
+                hi = *p.get(i)?;
+                i += 1;
+            }
+            if lo <= c && c <= hi {
+                matched = true;
+            }
+        } else if lo == c {
+            matched = true;
+        }
+    }
+
+    Some((matched != negate, i + 1))
+}
+
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else if base.ends_with('/') {
+        format!("{}{}", base, name)
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+/// Entries of a directory sorted like `ls`; `base` is "" for the current directory.
+fn entries(base: &str) -> Vec<String> {
+    let dir = if base.is_empty() { "." } else { base };
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(rd) => rd.flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort_by(|a, b| ls_cmp(a, b));
+    names
+}
+
+/// Everything below `base` for `**`: every non-hidden directory, plus the
+/// files too when `**` ends the pattern.
+fn walk(base: &str, with_files: bool, out: &mut Vec<String>) {
+    for name in entries(base) {
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = join(base, &name);
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+        if is_dir || with_files {
+            out.push(path.clone());
+        }
+        if is_dir {
+            walk(&path, with_files, out);
+        }
+    }
+}
+
+/// Expand a pathname pattern. Names starting with a dot are only matched by
+/// a pattern component that starts with a dot, and a `**` component matches
+/// any number of directories. Returns no paths if nothing matches.
+pub fn glob(pattern: &str) -> Vec<String> {
+    let dirs_only = pattern.ends_with('/');
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let mut paths = vec![if pattern.starts_with('/') { "/".to_string() } else { String::new() }];
+
+    for (idx, component) in components.iter().enumerate() {
+        let last = idx == components.len() - 1;
+        let mut next = Vec::new();
+
+        for base in &paths {
+            if *component == "**" {
+                // zero directories: the base itself, unless nothing else
+                // follows (`**/` lists the directories below it too)
+                if !last || (dirs_only && !base.is_empty()) {
+                    next.push(base.clone());
+                }
+                walk(base, last && !dirs_only, &mut next);
+                continue;
+            }
+
+            if !has_meta(component) {
+                let path = join(base, &unescape(component));
+                if Path::new(&path).symlink_metadata().is_ok() {
+                    next.push(path);
+                }
+                continue;
+            }
+
+            for name in entries(base) {
+                if name.starts_with('.') && !component.starts_with('.') {
+                    continue;
+                }
+                if fnmatch(component, &name) {
+                    next.push(join(base, &name));
+                }
+            }
+        }
+
+        // only directories can have more components below them
+        if !last || dirs_only {
+            next.retain(|p| p.is_empty() || Path::new(p).is_dir());
+        }
+        paths = next;
+    }
+
+    if components.is_empty() {
+        return Vec::new();
+    }
+    if dirs_only {
+        for p in paths.iter_mut().filter(|p| !p.ends_with('/')) {
+            p.push('/');
+        }
+    }
+    paths.sort_by(|a, b| ls_cmp(a, b));
+    paths.dedup();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::builtin::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn fnmatch_table() {
+        for (pattern, name, expected) in [
+            ("*", "anything", true),
+            ("*", "", true),
+            ("*.rs", "main.rs", true),
+            ("*.rs", "main.rs.bak", false),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "aXbY", false),
+            ("?", "a", true),
+            ("?", "", false),
+            ("??", "a", false),
+            ("[abc]", "b", true),
+            ("[abc]", "d", false),
+            ("[a-c]x", "bx", true),
+            ("[a-c]x", "dx", false),
+            ("[!a-c]", "d", true),
+            ("[!a-c]", "a", false),
+            ("[^a]", "b", true),
+            ("[]]", "]", true),
+            ("[a-]", "-", true),
+            ("[", "[", true),
+            ("a[", "a[", true),
+            (r"\*", "*", true),
+            (r"\*", "a", false),
+            (r"\?", "?", true),
+            (r"\[a]", "[a]", true),
+            (r"\[a]", "a", false),
+            (r"[\]]", "]", true),
+        ] {
+            assert_eq!(fnmatch(pattern, name), expected, "{} against {}", pattern, name);
+        }
+    }
+
+    #[test]
+    fn quoted_metacharacters_are_literal() {
+        assert!(has_meta("*.rs"));
+        assert!(has_meta("a?"));
+        assert!(has_meta("[ab]"));
+        assert!(!has_meta(r"\*.rs"));
+        assert!(!has_meta(r"a\?\[b]"));
+        assert!(!has_meta("plain"));
+        assert_eq!(unescape(r"\*a\\b\?"), r"*a\b?");
+    }
+
+    #[test]
+    fn expands_against_the_filesystem() {
+        let dir = scratch("glob");
+        for sub in ["sub/deep", ".hdir"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in ["A", "b.rs", ".hidden", "*", "sub/c.rs", "sub/.x.rs", "sub/deep/d.rs", ".hdir/e.rs"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let base = dir.display().to_string();
+        let glob_in = |pattern: &str| glob(&format!("{}/{}", base, pattern));
+        let paths = |names: &[&str]| -> Vec<String> { names.iter().map(|n| format!("{}/{}", base, n)).collect() };
+
+        let results = [
+            (glob_in("*"), paths(&["*", "A", "b.rs", "sub"])),
+            (glob_in(".*"), paths(&[".hdir", ".hidden"])),
+            (glob_in("*/"), paths(&["sub/"])),
+            (glob_in(r"\*"), paths(&["*"])),
+            (glob_in("**/*.rs"), paths(&["b.rs", "sub/c.rs", "sub/deep/d.rs"])),
+            (glob_in("**"), paths(&["*", "A", "b.rs", "sub", "sub/c.rs", "sub/deep", "sub/deep/d.rs"])),
+            (glob_in("sub/**/"), paths(&["sub/", "sub/deep/"])),
+            (glob_in("nothing*"), Vec::new()),
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+        for (found, expected) in results {
+            assert_eq!(found, expected);
+            assert!(found.windows(2).all(|w| ls_cmp(&w[0], &w[1]) != Ordering::Greater));
+        }
+    }
+}
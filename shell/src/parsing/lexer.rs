@@ -0,0 +1,434 @@
+use crate::parsing::ast::*;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    // a word as typed, quotes and backslashes included
+    Word(String),
+    // the fd and kind; the target is the next word
+    Redirect(i32, RedirectKind),
+    Pipe,
+    // `&&`
+    And,
+    // `||`
+    Or,
+    // `;`
+    Semi,
+    // `;;`, ends a case arm
+    DSemi,
+    // `&`, runs what comes before it in the background
+    Amp,
+    LParen,
+    RParen,
+    Newline,
+}
+
+impl TokenKind {
+    /// How the token is shown in a syntax error.
+    pub fn text(&self) -> String {
+        match self {
+            TokenKind::Word(w) => w.clone(),
+            TokenKind::Redirect(_, kind) => kind.operator().to_string(),
+            TokenKind::Pipe => "|".to_string(),
+            TokenKind::And => "&&".to_string(),
+            TokenKind::Or => "||".to_string(),
+            TokenKind::Semi => ";".to_string(),
+            TokenKind::DSemi => ";;".to_string(),
+            TokenKind::Amp => "&".to_string(),
+            TokenKind::LParen => "(".to_string(),
+            TokenKind::RParen => ")".to_string(),
+            TokenKind::Newline => "newline".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Read up to the bracket closing one that was just consumed and return
+/// what is between them. Quoted brackets do not count. Returns None if the
+/// input ends first.
+pub fn read_nested(chars: &mut Peekable<Chars>, open: char, close: char) -> Option<String> {
+    let mut inner = String::new();
+    let mut depth = 1;
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', q) if q != Some('\'') => {
+                inner.push(c);
+                if let Some(next) = chars.next() {
+                    inner.push(next);
+                }
+                continue;
+            }
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c == open => depth += 1,
+            (c, None) if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(inner);
+                }
+            }
+            _ => {}
+        }
+        inner.push(c);
+    }
+    None
+}
+
+/// Read up to the backquote closing one that was just consumed and return
+/// what is between them, backslashes included. Returns None if the input
+/// ends first.
+pub fn read_backquoted(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut inner = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => return Some(inner),
+            '\\' => {
+                inner.push(c);
+                if let Some(next) = chars.next() {
+                    inner.push(next);
+                }
+            }
+            _ => inner.push(c),
+        }
+    }
+    None
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.src[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Whether the next character ends an unquoted word.
+    fn at_word_end(&self) -> bool {
+        matches!(self.peek(), None | Some(' ' | '\t' | '\n' | ';' | '|' | '&' | '<' | '>' | '(' | ')'))
+    }
+
+    /// Copy a `$(...)`, `${...}` or `` `...` `` whose opening was just pushed
+    /// into `word`, up to and including its closing character.
+    fn read_substitution(&mut self, word: &mut String, open: char) -> Result<(), &'static str> {
+        let mut chars = self.src[self.pos..].chars().peekable();
+        let (inner, close, unclosed) = match open {
+            '(' => (read_nested(&mut chars, '(', ')'), ')', "cmdsubst> "),
+            '{' => (read_nested(&mut chars, '{', '}'), '}', "braceparam> "),
+            _ => (read_backquoted(&mut chars), '`', "bquote> "),
+        };
+        let inner = inner.ok_or(unclosed)?;
+        self.pos += inner.len() + close.len_utf8();
+        word.push_str(&inner);
+        word.push(close);
+        Ok(())
+    }
+
+    /// A backslash was just consumed: keep it with the character it quotes,
+    /// or drop both when it escapes a newline.
+    fn read_escape(&mut self, word: &mut String) -> Result<(), &'static str> {
+        match self.bump() {
+            Some('\n') => {}
+            Some(c) => {
+                word.push('\\');
+                word.push(c);
+            }
+            None => return Err("> "),
+        }
+        Ok(())
+    }
+
+    fn read_double_quoted(&mut self, word: &mut String) -> Result<(), &'static str> {
+        loop {
+            let c = self.bump().ok_or("dquote> ")?;
+            match c {
+                '\\' => self.read_escape(word)?,
+                '"' => {
+                    word.push(c);
+                    return Ok(());
+                }
+                '$' if matches!(self.peek(), Some('(' | '{')) => {
+                    let open = self.bump().unwrap_or_default();
+                    word.push('$');
+                    word.push(open);
+                    self.read_substitution(word, open)?;
+                }
+                '`' => {
+                    word.push(c);
+                    self.read_substitution(word, c)?;
+                }
+                _ => word.push(c),
+            }
+        }
+    }
+
+    fn read_word(&mut self) -> Result<String, &'static str> {
+        let mut word = String::new();
+        while !self.at_word_end() {
+            let c = self.bump().unwrap_or_default();
+            match c {
+                '\\' => self.read_escape(&mut word)?,
+                '\'' => {
+                    word.push(c);
+                    loop {
+                        let q = self.bump().ok_or("quote> ")?;
+                        word.push(q);
+                        if q == '\'' {
+                            break;
+                        }
+                    }
+                }
+                '"' => {
+                    word.push(c);
+                    self.read_double_quoted(&mut word)?;
+                }
+                '$' if matches!(self.peek(), Some('(' | '{')) => {
+                    let open = self.bump().unwrap_or_default();
+                    word.push('$');
+                    word.push(open);
+                    self.read_substitution(&mut word, open)?;
+                }
+                '`' => {
+                    word.push(c);
+                    self.read_substitution(&mut word, c)?;
+                }
+                _ => word.push(c),
+            }
+        }
+        Ok(word)
+    }
+
+    /// Read the rest of a redirection operator whose first character (`>` or
+    /// `<`) was just consumed. The target word, if any, is the next token.
+    fn read_redirect(&mut self, op: char, fd: Option<i32>) -> TokenKind {
+        let default_fd = if op == '<' { 0 } else { 1 };
+        let mut kind = if op == '<' { RedirectKind::Input } else { RedirectKind::Output };
+
+        match self.peek() {
+            Some('>') if op == '>' => {
+                self.bump();
+                kind = RedirectKind::Append;
+            }
+            Some('&') => {
+                self.bump();
+                let mut digits = String::new();
+                while let Some(d) = self.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(d);
+                    self.bump();
+                }
+                if let Ok(n) = digits.parse() {
+                    kind = RedirectKind::Dup(n);
+                } else if self.peek() == Some('-') {
+                    self.bump();
+                    kind = RedirectKind::Close;
+                } else if op == '>' && fd.is_none() {
+                    // `>&file` is the old spelling of `&>file`
+                    kind = RedirectKind::Both;
+                }
+            }
+            _ => {}
+        }
+
+        TokenKind::Redirect(fd.unwrap_or(default_fd), kind)
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, &'static str> {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+        // a `#` starting a word comments out the rest of the line
+        if self.peek() == Some('#') {
+            while self.peek().is_some_and(|c| c != '\n') {
+                self.bump();
+            }
+        }
+
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        let kind = match c {
+            ';' if self.peek_second() == Some(';') => {
+                self.pos += 2;
+                TokenKind::DSemi
+            }
+            '\n' | ';' | '(' | ')' => {
+                self.bump();
+                match c {
+                    '\n' => TokenKind::Newline,
+                    ';' => TokenKind::Semi,
+                    '(' => TokenKind::LParen,
+                    _ => TokenKind::RParen,
+                }
+            }
+            '|' => {
+                self.bump();
+                if self.peek() == Some('|') {
+                    self.bump();
+                    TokenKind::Or
+                } else {
+                    TokenKind::Pipe
+                }
+            }
+            '&' if self.peek_second() == Some('&') => {
+                self.pos += 2;
+                TokenKind::And
+            }
+            '&' if self.peek_second() == Some('>') => {
+                self.pos += 2;
+                let mut kind = RedirectKind::Both;
+                if self.peek() == Some('>') {
+                    self.bump();
+                    kind = RedirectKind::BothAppend;
+                }
+                TokenKind::Redirect(1, kind)
+            }
+            '&' => {
+                self.bump();
+                TokenKind::Amp
+            }
+            '<' | '>' => {
+                self.bump();
+                self.read_redirect(c, None)
+            }
+            _ => {
+                let word = self.read_word()?;
+                if word.is_empty() {
+                    // only an escaped newline
+                    return self.next_token();
+                }
+                match self.peek() {
+                    // digits written right before the operator name the fd (`2>`);
+                    // a quoted digit keeps its quotes here so it never matches
+                    Some(op @ ('<' | '>')) if word.chars().all(|d| d.is_ascii_digit()) => {
+                        self.bump();
+                        self.read_redirect(op, word.parse().ok())
+                    }
+                    _ => TokenKind::Word(word),
+                }
+            }
+        };
+
+        Ok(Some(Token { kind, span: Span { start, end: self.pos } }))
+    }
+}
+
+/// Split source text into tokens. Words keep their quotes; they are removed
+/// during expansion. When the text ends inside a quote or substitution, the
+/// continuation prompt for it is returned instead.
+pub fn tokenize(src: &str) -> Result<Vec<Token>, &'static str> {
+    let mut lexer = Lexer { src, pos: 0 };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        tokenize(src).unwrap().into_iter().map(|t| t.kind).collect()
+    }
+
+    fn word(w: &str) -> TokenKind {
+        TokenKind::Word(w.to_string())
+    }
+
+    #[test]
+    fn quoted_and_escaped_operators_stay_in_words() {
+        assert_eq!(
+            kinds(r#"echo 'a;b' "c|d" e\&f;g"#),
+            vec![word("echo"), word("'a;b'"), word(r#""c|d""#), word(r"e\&f"), TokenKind::Semi, word("g")]
+        );
+        assert_eq!(kinds(r#"echo "a && b" 'c || d'"#), vec![word("echo"), word(r#""a && b""#), word("'c || d'")]);
+        assert_eq!(kinds("echo $(a | b) `c; d`"), vec![word("echo"), word("$(a | b)"), word("`c; d`")]);
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            kinds("a&&b||c|d;e&\n(f);;"),
+            vec![
+                word("a"),
+                TokenKind::And,
+                word("b"),
+                TokenKind::Or,
+                word("c"),
+                TokenKind::Pipe,
+                word("d"),
+                TokenKind::Semi,
+                word("e"),
+                TokenKind::Amp,
+                TokenKind::Newline,
+                TokenKind::LParen,
+                word("f"),
+                TokenKind::RParen,
+                TokenKind::DSemi,
+            ]
+        );
+    }
+
+    #[test]
+    fn redirects() {
+        assert_eq!(
+            kinds("cmd 2>&1 >>out <in &>all 2>&-"),
+            vec![
+                word("cmd"),
+                TokenKind::Redirect(2, RedirectKind::Dup(1)),
+                TokenKind::Redirect(1, RedirectKind::Append),
+                word("out"),
+                TokenKind::Redirect(0, RedirectKind::Input),
+                word("in"),
+                TokenKind::Redirect(1, RedirectKind::Both),
+                word("all"),
+                TokenKind::Redirect(2, RedirectKind::Close),
+            ]
+        );
+        // a quoted digit is a word, not an fd
+        assert_eq!(kinds(r#""2">x"#), vec![word(r#""2""#), TokenKind::Redirect(1, RedirectKind::Output), word("x")]);
+    }
+
+    #[test]
+    fn dashes_are_plain_words() {
+        assert_eq!(kinds("rm -- -file"), vec![word("rm"), word("--"), word("-file")]);
+        assert_eq!(kinds("cat - a"), vec![word("cat"), word("-"), word("a")]);
+    }
+
+    #[test]
+    fn unfinished_input_asks_for_more() {
+        assert_eq!(tokenize("echo 'abc"), Err("quote> "));
+        assert_eq!(tokenize("echo \"abc"), Err("dquote> "));
+        assert_eq!(tokenize("echo $(ls"), Err("cmdsubst> "));
+        assert_eq!(tokenize("echo ${x"), Err("braceparam> "));
+        assert_eq!(tokenize("echo `ls"), Err("bquote> "));
+        assert_eq!(tokenize("echo abc\\"), Err("> "));
+    }
+
+    #[test]
+    fn spans_cover_the_token() {
+        let tokens = tokenize("ls  -l >out").unwrap();
+        let spans: Vec<(usize, usize)> = tokens.iter().map(|t| (t.span.start, t.span.end)).collect();
+        assert_eq!(spans, vec![(0, 2), (4, 6), (7, 8), (8, 11)]);
+    }
+}
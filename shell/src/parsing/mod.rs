@@ -1 +1,6 @@
+pub mod ast;
+pub mod expand;
+pub mod glob;
+pub mod lexer;
+pub mod parser;
 pub mod split_save;
\ No newline at end of file
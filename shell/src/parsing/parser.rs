@@ -0,0 +1,645 @@
+use crate::parsing::ast::*;
+use crate::parsing::lexer::*;
+use crate::variables::shell::*;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Words only special in command position, which are never taken for an
+/// alias there.
+pub const RESERVED: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "while", "until", "for", "in", "do", "done", "case", "esac",
+    "{", "}", "!",
+];
+
+/// Words that continue or close a compound command and so cannot start a
+/// command.
+const CLOSERS: &[&str] = &["}", "then", "elif", "else", "fi", "do", "done", "esac"];
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// The source ends inside an unfinished construct. Holds the
+    /// continuation prompt to read the rest with.
+    Incomplete(&'static str),
+    /// A token that cannot appear where it was found.
+    Syntax { token: String, span: Span },
+}
+
+impl ParseError {
+    /// Print the error, pointing at the offending token in `src`.
+    pub fn report(&self, src: &str) {
+        eprint!("{}", self.message(src));
+    }
+
+    /// The text `report` prints: what went wrong and, for a syntax error,
+    /// the line with a caret under the token.
+    pub fn message(&self, src: &str) -> String {
+        let (token, span) = match self {
+            ParseError::Incomplete(_) => return "0-shell: syntax error: unexpected end of file\n".to_string(),
+            ParseError::Syntax { token, span } => (token, *span),
+        };
+
+        let line_start = src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[span.start..].find('\n').map_or(src.len(), |i| span.start + i);
+        let mut message = if src.contains('\n') {
+            let line = src[..span.start].matches('\n').count() + 1;
+            format!("0-shell: line {}: syntax error near unexpected token `{}'\n", line, token)
+        } else {
+            format!("0-shell: syntax error near unexpected token `{}'\n", token)
+        };
+        let col = src[line_start..span.start].chars().count();
+        let width = src[span.start..span.end.min(line_end)].chars().count().max(1);
+        message.push_str(&format!("  {}\n", &src[line_start..line_end]));
+        message.push_str(&format!("  {}{}\n", " ".repeat(col), "^".repeat(width)));
+        message
+    }
+}
+
+/// Split `NAME=value` into its name and value.
+pub fn split_assignment(word: &str) -> Option<(&str, &str)> {
+    let (name, value) = word.split_once('=')?;
+    if is_valid_name(name) { Some((name, value)) } else { None }
+}
+
+/// What alias expansion left on a token.
+#[derive(Debug, Clone, Default)]
+struct AliasMark {
+    // the aliases the token came out of, which it must not expand to again
+    from: Vec<String>,
+    // the token follows an alias whose value ends in a blank, so it is
+    // checked for an alias too even though it is not the command name
+    after_blank: bool,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    // one per token
+    marks: Vec<AliasMark>,
+    pos: usize,
+    src: &'a str,
+    aliases: &'a BTreeMap<String, String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Start of the next token, or the end of the source.
+    fn here(&self) -> Span {
+        let end = self.src.len();
+        self.tokens.get(self.pos).map_or(Span { start: end, end }, |t| t.span)
+    }
+
+    /// End of the last consumed token.
+    fn prev_end(&self) -> usize {
+        self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)).map_or(0, |t| t.span.end)
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span { start, end: self.prev_end().max(start) }
+    }
+
+    /// Whether the next token is the unquoted word `word`.
+    fn at_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(TokenKind::Word(w)) if w == word)
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&TokenKind::Newline) {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        let token = self.peek().map_or("newline".to_string(), |t| t.text());
+        ParseError::Syntax { token, span: self.here() }
+    }
+
+    /// Consume the word closing a construct; at the end of input, ask for
+    /// more with `prompt`.
+    fn expect_word(&mut self, word: &str, prompt: &'static str) -> Result<(), ParseError> {
+        if self.peek().is_none() {
+            return Err(ParseError::Incomplete(prompt));
+        }
+        if !self.at_word(word) {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// If the next token is a word naming an alias, replace it with the
+    /// tokens of the alias value. Returns whether it did.
+    fn expand_alias(&mut self) -> Result<bool, ParseError> {
+        let Some(TokenKind::Word(word)) = self.peek() else {
+            return Ok(false);
+        };
+        let mark = &self.marks[self.pos];
+        let value = match self.aliases.get(word) {
+            Some(value) if !RESERVED.contains(&word.as_str()) && !mark.from.contains(word) => value,
+            _ => return Ok(false),
+        };
+        // an alias is not a place to leave a quote open
+        let Ok(tokens) = tokenize(value) else {
+            return Err(self.unexpected());
+        };
+
+        let mut from = mark.from.clone();
+        from.push(word.clone());
+        let span = self.here();
+        let count = tokens.len();
+        let chains = value.ends_with([' ', '\t']);
+        self.tokens.splice(self.pos..=self.pos, tokens.into_iter().map(|t| Token { kind: t.kind, span }));
+        self.marks.splice(self.pos..=self.pos, vec![AliasMark { from, after_blank: false }; count]);
+        if chains && let Some(next) = self.marks.get_mut(self.pos + count) {
+            next.after_blank = true;
+        }
+        Ok(true)
+    }
+
+    fn at_list_end(&self, closers: &[&str]) -> bool {
+        match self.peek() {
+            None | Some(TokenKind::RParen | TokenKind::DSemi) => true,
+            Some(TokenKind::Word(w)) => closers.contains(&w.as_str()),
+            _ => false,
+        }
+    }
+
+    /// And-or lists up to the end of input, a `)` or one of `closers`.
+    fn parse_list(&mut self, closers: &[&str]) -> Result<List, ParseError> {
+        let mut list = List::default();
+        loop {
+            self.skip_newlines();
+            if self.at_list_end(closers) {
+                break;
+            }
+            let mut item = self.parse_and_or()?;
+            item.background = self.peek() == Some(&TokenKind::Amp);
+            list.items.push(item);
+            match self.peek() {
+                Some(TokenKind::Semi | TokenKind::Amp | TokenKind::Newline) => self.pos += 1,
+                _ => break,
+            }
+        }
+        Ok(list)
+    }
+
+    fn parse_and_or(&mut self) -> Result<AndOrList, ParseError> {
+        let first = self.parse_pipeline()?;
+        let mut rest = Vec::new();
+        loop {
+            let (op, prompt) = match self.peek() {
+                Some(TokenKind::And) => (AndOr::And, "cmdand> "),
+                Some(TokenKind::Or) => (AndOr::Or, "cmdor> "),
+                _ => break,
+            };
+            self.pos += 1;
+            self.skip_newlines();
+            if self.peek().is_none() {
+                return Err(ParseError::Incomplete(prompt));
+            }
+            rest.push((op, self.parse_pipeline()?));
+        }
+        Ok(AndOrList { first, rest, background: false })
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let start = self.here().start;
+        let negated = self.at_word("!");
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut commands = vec![self.parse_command()?];
+        while self.peek() == Some(&TokenKind::Pipe) {
+            self.pos += 1;
+            self.skip_newlines();
+            if self.peek().is_none() {
+                return Err(ParseError::Incomplete("pipe> "));
+            }
+            commands.push(self.parse_command()?);
+        }
+
+        let span = self.span_from(start);
+        let text = self.src.get(span.start..span.end).unwrap_or_default().to_string();
+        Ok(Pipeline { negated, commands, span, text })
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParseError> {
+        while self.expand_alias()? {}
+        let start = self.here().start;
+        let body = match self.peek() {
+            Some(TokenKind::Word(w)) if w == "{" => {
+                self.pos += 1;
+                let list = self.parse_list(&["}"])?;
+                if list.items.is_empty() && self.peek().is_some() {
+                    return Err(self.unexpected());
+                }
+                self.expect_word("}", "cursh> ")?;
+                CompoundCommand::Group(list)
+            }
+            Some(TokenKind::LParen) => {
+                self.pos += 1;
+                let list = self.parse_list(&[])?;
+                match self.peek() {
+                    None => return Err(ParseError::Incomplete("subsh> ")),
+                    Some(TokenKind::RParen) if !list.items.is_empty() => self.pos += 1,
+                    _ => return Err(self.unexpected()),
+                }
+                CompoundCommand::Subshell(list)
+            }
+            Some(TokenKind::Word(w)) if w == "if" => self.parse_if()?,
+            Some(TokenKind::Word(w)) if w == "while" || w == "until" => self.parse_while()?,
+            Some(TokenKind::Word(w)) if w == "for" => self.parse_for()?,
+            Some(TokenKind::Word(w)) if w == "case" => self.parse_case()?,
+            Some(TokenKind::Word(w)) if CLOSERS.contains(&w.as_str()) => return Err(self.unexpected()),
+            Some(TokenKind::Word(_)) if self.at_function_def() => return self.parse_function(),
+            _ => return self.parse_simple().map(Command::Simple),
+        };
+
+        let mut redirects = Vec::new();
+        while matches!(self.peek(), Some(TokenKind::Redirect(..))) {
+            redirects.push(self.parse_redirect()?);
+        }
+        Ok(Command::Compound { body, redirects, span: self.span_from(start) })
+    }
+
+    /// Whether the next tokens are `name ( )`.
+    fn at_function_def(&self) -> bool {
+        let kinds: Vec<&TokenKind> = self.tokens.iter().skip(self.pos).take(3).map(|t| &t.kind).collect();
+        match kinds[..] {
+            [TokenKind::Word(name), TokenKind::LParen, TokenKind::RParen] => {
+                !name.contains(['\'', '"', '\\', '$', '`', '='])
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_function(&mut self) -> Result<Command, ParseError> {
+        let start = self.here().start;
+        let Some(Token { kind: TokenKind::Word(name), .. }) = self.next() else {
+            unreachable!("parse_function called without a name");
+        };
+        self.pos += 2;
+        self.skip_newlines();
+        if self.peek().is_none() {
+            return Err(ParseError::Incomplete("function> "));
+        }
+        // the body has to be a compound command, usually `{ ...; }`
+        let body_start = self.pos;
+        let body = self.parse_command()?;
+        if !matches!(body, Command::Compound { .. }) {
+            self.pos = body_start;
+            return Err(self.unexpected());
+        }
+        Ok(Command::FunctionDef { name, body: Rc::new(body), span: self.span_from(start) })
+    }
+
+    /// A list that must not be empty, ended by one of `closers`.
+    fn parse_body(&mut self, closers: &[&str], prompt: &'static str) -> Result<List, ParseError> {
+        let list = self.parse_list(closers)?;
+        if self.peek().is_none() {
+            return Err(ParseError::Incomplete(prompt));
+        }
+        if list.items.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(list)
+    }
+
+    fn parse_if(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+        let mut branches = Vec::new();
+        let mut else_body = None;
+        let mut prompt = "if> ";
+        loop {
+            let cond = self.parse_body(&["then"], prompt)?;
+            self.expect_word("then", prompt)?;
+            let body = self.parse_body(&["elif", "else", "fi"], "then> ")?;
+            branches.push((cond, body));
+            if self.at_word("elif") {
+                self.pos += 1;
+                prompt = "elif> ";
+                continue;
+            }
+            if self.at_word("else") {
+                self.pos += 1;
+                else_body = Some(self.parse_body(&["fi"], "else> ")?);
+            }
+            self.expect_word("fi", "if> ")?;
+            break;
+        }
+        Ok(CompoundCommand::If { branches, else_body })
+    }
+
+    fn parse_while(&mut self) -> Result<CompoundCommand, ParseError> {
+        let until = self.at_word("until");
+        let prompt = if until { "until> " } else { "while> " };
+        self.pos += 1;
+        let cond = self.parse_body(&["do"], prompt)?;
+        self.expect_word("do", prompt)?;
+        let body = self.parse_body(&["done"], prompt)?;
+        self.expect_word("done", prompt)?;
+        Ok(CompoundCommand::While { cond, body, until })
+    }
+
+    fn parse_for(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+        let name = match self.peek() {
+            None => return Err(ParseError::Incomplete("for> ")),
+            Some(TokenKind::Word(w)) if is_valid_name(w) => w.clone(),
+            _ => return Err(self.unexpected()),
+        };
+        self.pos += 1;
+
+        self.skip_newlines();
+        let mut words = None;
+        if self.at_word("in") {
+            self.pos += 1;
+            let mut list = Vec::new();
+            while let Some(TokenKind::Word(text)) = self.peek() {
+                list.push(Word { text: text.clone(), span: self.here() });
+                self.pos += 1;
+            }
+            words = Some(list);
+            match self.peek() {
+                None => return Err(ParseError::Incomplete("for> ")),
+                Some(TokenKind::Semi | TokenKind::Newline) => self.pos += 1,
+                _ => return Err(self.unexpected()),
+            }
+        } else if self.peek() == Some(&TokenKind::Semi) {
+            self.pos += 1;
+        }
+
+        self.skip_newlines();
+        self.expect_word("do", "for> ")?;
+        let body = self.parse_body(&["done"], "for> ")?;
+        self.expect_word("done", "for> ")?;
+        Ok(CompoundCommand::For { name, words, body })
+    }
+
+    fn parse_case(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+        let word = match self.next() {
+            None => return Err(ParseError::Incomplete("case> ")),
+            Some(Token { kind: TokenKind::Word(text), span }) => Word { text, span },
+            Some(_) => {
+                self.pos -= 1;
+                return Err(self.unexpected());
+            }
+        };
+        self.skip_newlines();
+        self.expect_word("in", "case> ")?;
+
+        let mut arms = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.peek().is_none() {
+                return Err(ParseError::Incomplete("case> "));
+            }
+            if self.at_word("esac") {
+                self.pos += 1;
+                break;
+            }
+            if self.peek() == Some(&TokenKind::LParen) {
+                self.pos += 1;
+            }
+
+            let mut patterns = Vec::new();
+            loop {
+                match self.next() {
+                    None => return Err(ParseError::Incomplete("case> ")),
+                    Some(Token { kind: TokenKind::Word(text), span }) => patterns.push(Word { text, span }),
+                    Some(_) => {
+                        self.pos -= 1;
+                        return Err(self.unexpected());
+                    }
+                }
+                match self.peek() {
+                    Some(TokenKind::Pipe) => self.pos += 1,
+                    Some(TokenKind::RParen) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    None => return Err(ParseError::Incomplete("case> ")),
+                    _ => return Err(self.unexpected()),
+                }
+            }
+
+            // an arm may be empty: `a) ;;`
+            let body = self.parse_list(&["esac"])?;
+            arms.push(CaseArm { patterns, body });
+            match self.peek() {
+                Some(TokenKind::DSemi) => self.pos += 1,
+                None => return Err(ParseError::Incomplete("case> ")),
+                _ if self.at_word("esac") => {}
+                _ => return Err(self.unexpected()),
+            }
+        }
+        Ok(CompoundCommand::Case { word, arms })
+    }
+
+    fn parse_redirect(&mut self) -> Result<Redirect, ParseError> {
+        let Some(Token { kind: TokenKind::Redirect(fd, kind), span }) = self.next() else {
+            unreachable!("parse_redirect called on a non-redirect token");
+        };
+        let mut target = None;
+        if kind.needs_target() {
+            match self.peek() {
+                Some(TokenKind::Word(_)) => {
+                    let token = self.next().unwrap();
+                    if let TokenKind::Word(text) = token.kind {
+                        target = Some(Word { text, span: token.span });
+                    }
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+        Ok(Redirect { fd, kind, target, span: self.span_from(span.start) })
+    }
+
+    fn parse_simple(&mut self) -> Result<SimpleCommand, ParseError> {
+        let start = self.here().start;
+        let mut cmd = SimpleCommand {
+            assignments: Vec::new(),
+            words: Vec::new(),
+            redirects: Vec::new(),
+            span: Span::default(),
+        };
+
+        loop {
+            match self.peek() {
+                Some(TokenKind::Word(_)) => {
+                    if (cmd.words.is_empty() || self.marks[self.pos].after_blank) && self.expand_alias()? {
+                        continue;
+                    }
+                    let token = self.next().unwrap();
+                    let TokenKind::Word(text) = token.kind else { unreachable!() };
+                    // assignments only count before the command name
+                    if cmd.words.is_empty()
+                        && let Some((name, value)) = split_assignment(&text)
+                    {
+                        let value_start = (token.span.start + name.len() + 1).min(token.span.end);
+                        cmd.assignments.push(Assignment {
+                            name: name.to_string(),
+                            value: Word { text: value.to_string(), span: Span { start: value_start, end: token.span.end } },
+                            span: token.span,
+                        });
+                        continue;
+                    }
+                    cmd.words.push(Word { text, span: token.span });
+                }
+                Some(TokenKind::Redirect(..)) => cmd.redirects.push(self.parse_redirect()?),
+                _ => break,
+            }
+        }
+
+        if cmd.assignments.is_empty() && cmd.words.is_empty() && cmd.redirects.is_empty() {
+            return Err(self.unexpected());
+        }
+        cmd.span = self.span_from(start);
+        Ok(cmd)
+    }
+}
+
+/// Parse source text into a command list, expanding `aliases` in command
+/// position. `ParseError::Incomplete` means the text is fine so far but
+/// stops in the middle of a command.
+pub fn parse_program(src: &str, aliases: &BTreeMap<String, String>) -> Result<List, ParseError> {
+    let tokens = tokenize(src).map_err(ParseError::Incomplete)?;
+    let marks = vec![AliasMark::default(); tokens.len()];
+    let mut parser = Parser { tokens, marks, pos: 0, src, aliases };
+    let list = parser.parse_list(&[])?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The words of every simple command, in order.
+    fn commands(src: &str, aliases: &BTreeMap<String, String>) -> Vec<Vec<String>> {
+        let list = parse_program(src, aliases).unwrap();
+        let mut found = Vec::new();
+        for item in &list.items {
+            let pipelines = std::iter::once(&item.first).chain(item.rest.iter().map(|(_, p)| p));
+            for command in pipelines.flat_map(|p| &p.commands) {
+                if let Command::Simple(simple) = command {
+                    found.push(simple.words.iter().map(|w| w.text.clone()).collect());
+                }
+            }
+        }
+        found
+    }
+
+    fn argv(src: &str) -> Vec<Vec<String>> {
+        commands(src, &BTreeMap::new())
+    }
+
+    fn incomplete(src: &str) -> ParseError {
+        parse_program(src, &BTreeMap::new()).unwrap_err()
+    }
+
+    #[test]
+    fn arguments_keep_their_order() {
+        assert_eq!(argv("echo -- -x"), vec![vec!["echo", "--", "-x"]]);
+        assert_eq!(argv("rm -- -file"), vec![vec!["rm", "--", "-file"]]);
+        assert_eq!(argv("cat a - b"), vec![vec!["cat", "a", "-", "b"]]);
+        assert_eq!(argv("ls -l dir -a"), vec![vec!["ls", "-l", "dir", "-a"]]);
+    }
+
+    #[test]
+    fn quoted_operators_do_not_split_commands() {
+        assert_eq!(argv(r#"echo "a && b" 'c | d' e\;f"#), vec![vec!["echo", r#""a && b""#, "'c | d'", r"e\;f"]]);
+        assert_eq!(argv("a | b && c; d &"), vec![vec!["a"], vec!["b"], vec!["c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn background_and_pipeline_text() {
+        let list = parse_program("sleep 1 | cat && true & echo", &BTreeMap::new()).unwrap();
+        assert!(list.items[0].background);
+        assert!(!list.items[1].background);
+        assert_eq!(list.items[0].text(), "sleep 1 | cat && true");
+    }
+
+    #[test]
+    fn unfinished_commands_ask_for_more() {
+        for (src, prompt) in [
+            ("ls |", "pipe> "),
+            ("a &&", "cmdand> "),
+            ("a ||", "cmdor> "),
+            ("{ ls", "cursh> "),
+            ("( ls", "subsh> "),
+            ("f()", "function> "),
+            ("if true; then ls", "then> "),
+            ("while true", "while> "),
+            ("until true", "until> "),
+            ("for i in a b", "for> "),
+            ("case x in", "case> "),
+            ("echo 'x", "quote> "),
+        ] {
+            assert_eq!(incomplete(src), ParseError::Incomplete(prompt), "{}", src);
+        }
+    }
+
+    #[test]
+    fn report_points_at_the_token() {
+        let src = "echo a; ;; b";
+        let err = incomplete(src);
+        assert_eq!(err, ParseError::Syntax { token: ";;".to_string(), span: Span { start: 8, end: 10 } });
+        assert_eq!(
+            err.message(src),
+            "0-shell: syntax error near unexpected token `;;'\n  echo a; ;; b\n          ^^\n"
+        );
+    }
+
+    #[test]
+    fn report_names_the_line() {
+        let src = "ls\necho ok\n  fi";
+        assert_eq!(
+            incomplete(src).message(src),
+            "0-shell: line 3: syntax error near unexpected token `fi'\n    fi\n    ^^\n"
+        );
+        assert_eq!(incomplete("ls |").message("ls |"), "0-shell: syntax error: unexpected end of file\n");
+    }
+
+    fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn aliases_expand_in_command_position() {
+        let aliases = aliases(&[("ll", "ls -l"), ("x", "hello")]);
+        assert_eq!(
+            commands("ll dir; echo ll | ll", &aliases),
+            vec![vec!["ls", "-l", "dir"], vec!["echo", "ll"], vec!["ls", "-l"]]
+        );
+        // quoted names and arguments are left alone
+        assert_eq!(commands("'ll' x", &aliases), vec![vec!["'ll'", "x"]]);
+    }
+
+    #[test]
+    fn trailing_blank_chains_to_the_next_word() {
+        let aliases = aliases(&[("e", "echo "), ("s", "sudo "), ("x", "hello"), ("q", "echo")]);
+        assert_eq!(commands("e x", &aliases), vec![vec!["echo", "hello"]]);
+        assert_eq!(commands("s e x", &aliases), vec![vec!["sudo", "echo", "hello"]]);
+        assert_eq!(commands("q x", &aliases), vec![vec!["echo", "x"]]);
+    }
+
+    #[test]
+    fn aliases_do_not_expand_themselves() {
+        let aliases = aliases(&[("ls", "ls -F"), ("ll", "ls -l"), ("a", "b"), ("b", "a")]);
+        assert_eq!(commands("ls", &aliases), vec![vec!["ls", "-F"]]);
+        assert_eq!(commands("ll", &aliases), vec![vec!["ls", "-F", "-l"]]);
+        assert_eq!(commands("a; b", &aliases), vec![vec!["a"], vec!["b"]]);
+    }
+}
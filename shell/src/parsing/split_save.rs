@@ -1,153 +1,130 @@
+use crate::editor::complete::*;
+use crate::editor::line_editor::*;
+use crate::editor::terminal::*;
+use crate::exec::prompt::*;
+use crate::parsing::ast::*;
+use crate::parsing::parser::*;
+use crate::variables::shell::*;
 use std::io::{self, Write};
-use crate::variables::var::*;
 
-pub fn remove_all_quotes(s: &str) -> String {
-    s.chars()
-        .filter(|&c| c != '\'' && c != '"')
-        .collect()
-}
-
-#[derive(PartialEq, Copy, Clone)]
-enum QuoteState {
-    None,
-    Single,
-    Double,
-}
-
-fn quote_state(s: &str) -> QuoteState {
-    let mut state = QuoteState::None;
-    let mut escape = false;
-
-    for c in s.chars() {
-        if escape {
-            escape = false;
-            continue;
+/// Read a line from stdin one byte at a time.
+///
+/// std's `Stdin` reads ahead into its own buffer; whatever it grabbed past the
+/// current line would be lost to (or duplicated in) the commands we start, so
+/// the shell never reads more than the line it is about to run.
+fn read_line(buf: &mut String) -> io::Result<usize> {
+    let mut bytes = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        let n = unsafe { libc::read(0, byte.as_mut_ptr().cast(), 1) };
+        if n < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
         }
-
-        if c == '\\' {
-            escape = true;
-            continue;
+        if n == 0 {
+            break;
         }
-
-        match (c, state) {
-            ('\'', QuoteState::None) => state = QuoteState::Single,
-            ('\'', QuoteState::Single) => state = QuoteState::None,
-            ('"', QuoteState::None) => state = QuoteState::Double,
-            ('"', QuoteState::Double) => state = QuoteState::None,
-            _ => {}
+        bytes.push(byte[0]);
+        if byte[0] == b'\n' {
+            break;
         }
     }
-
-    state
+    buf.push_str(&String::from_utf8_lossy(&bytes));
+    Ok(bytes.len())
 }
 
-pub fn flatten_flags(flags: Vec<String>) -> Vec<String> {	
-    let mut result = Vec::new();
-    for flag in flags {
-        for c in flag.trim_start_matches('-').chars() {
-            result.push(c.to_string());
-        }
-    }
-    result
+/// Where command lines come from.
+pub struct Input {
+    // the remaining lines of a script or `-c` string; None reads stdin
+    lines: Option<std::vec::IntoIter<String>>,
+    // only show prompts when someone is typing
+    prompt: bool,
+    // used instead of plain reads when the typing happens on a terminal
+    editor: Option<LineEditor>,
 }
 
-pub fn split_save(mut input: String) -> Var {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    input = input.trim_end().to_string();
-    let mut state = quote_state(&input);
-
-    while state != QuoteState::None {
-        match state {
-            QuoteState::Single => print!("quote> "),
-            QuoteState::Double => print!("dquote> "),
-            QuoteState::None => break,
-        }
-        stdout.flush().unwrap();
-
-        let mut next_line = String::new();
-        if stdin.read_line(&mut next_line).is_err() {
-            break;
-        }
-        input.push('\n');
-        input.push_str(next_line.trim_end());
-
-        state = quote_state(&input);
+impl Input {
+    pub fn stdin(prompt: bool) -> Self {
+        let editor = (prompt && is_terminal()).then(LineEditor::default);
+        Input { lines: None, prompt, editor }
     }
 
-    if input.is_empty() {
-        return Var::new();
+    pub fn text(text: &str) -> Self {
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        Input { lines: Some(lines.into_iter()), prompt: false, editor: None }
     }
 
-    let tokens = tokenize(&input);
-    if tokens.is_empty() {
-        return Var::new();
+    /// Whether someone is typing the commands.
+    pub fn interactive(&self) -> bool {
+        self.prompt
     }
 
-    let command = remove_all_quotes(&tokens[0]);
-    let mut flags = Vec::new();
-    let mut args = Vec::new();
+    /// Print `prompt` if prompts are shown, then read the next line without
+    /// its newline. Returns None at the end of input.
+    ///
+    /// The line editor keeps reading while the command is unfinished, so
+    /// what it returns can span several lines. Ctrl-C in it gives an empty
+    /// line.
+    pub fn next_line(&mut self, prompt: &str, shell: &Shell) -> Option<String> {
+        if let Some(lines) = &mut self.lines {
+            return lines.next();
+        }
+        if let Some(editor) = &mut self.editor {
+            let continuation = |text: &str| match parse_program(text, &shell.aliases) {
+                Err(ParseError::Incomplete(prompt)) => Some(continuation_prompt(shell, prompt)),
+                _ => None,
+            };
+            let completer = |text: &str, cursor: usize| complete(shell, text, cursor);
+            match editor.read_line(prompt, shell.history.lines(), &continuation, &completer) {
+                Ok(line) => return line,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Some(String::new()),
+                // the terminal cannot do raw mode; read plain lines instead
+                Err(_) => self.editor = None,
+            }
+        }
+        if self.prompt {
+            print!("{}", prompt);
+            io::stdout().flush().unwrap();
+        }
 
-    for token in tokens.iter().skip(1) {
-        if token.starts_with('-') {
-            flags.push(remove_all_quotes(token));
-        } else {
-            args.push(remove_all_quotes(token));
+        let mut line = String::new();
+        match read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                }
+                Some(line)
+            }
         }
     }
-
-    let flags = flatten_flags(flags);
-    Var { command, flags, args }
 }
 
-fn tokenize(s: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut current = String::new();
-    let mut chars = s.chars().peekable();
-    let mut in_quotes = None;
-    let mut escape = false;
-
-    while let Some(&c) = chars.peek() {
-        match c {
-            '\\' if !escape => {
-                escape = true;
-                chars.next();
-            }
-            '"' | '\'' if !escape => {
-                let quote = c;
-                if in_quotes.is_none() {
-                    in_quotes = Some(quote);
-                    chars.next();
-                } else if in_quotes == Some(quote) {
-                    in_quotes = None;
-                    chars.next();
-                } else {
-                    current.push(c);
-                    chars.next();
+/// Read (continuing over several lines if needed) and parse one command
+/// line. `source` holds the first line and gets the rest appended. Returns
+/// None on a syntax error.
+pub fn split_save(input: &mut Input, source: &mut String, shell: &Shell) -> Option<List> {
+    loop {
+        match parse_program(source, &shell.aliases) {
+            Ok(list) => return Some(list),
+            // an open quote, a trailing `|` and the like go on on the next line
+            Err(ParseError::Incomplete(prompt)) => match input.next_line(&continuation_prompt(shell, prompt), shell) {
+                Some(line) => {
+                    source.push('\n');
+                    source.push_str(&line);
                 }
-            }
-            _ => {
-                if escape {
-                    current.push(c);
-                    escape = false;
-                    chars.next();
-                } else if c == ' ' && in_quotes.is_none() {
-                    if !current.is_empty() {
-                        tokens.push(current.clone());
-                        current.clear();
-                    }
-                    chars.next();
-                } else {
-                    current.push(c);
-                    chars.next();
+                None => {
+                    ParseError::Incomplete(prompt).report(source);
+                    return None;
                 }
+            },
+            Err(e) => {
+                e.report(source);
+                return None;
             }
         }
     }
-
-    if !current.is_empty() {
-        tokens.push(current);
-    }
-
-    tokens
 }
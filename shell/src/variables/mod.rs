@@ -1 +1 @@
-pub mod var;
\ No newline at end of file
+pub mod shell;
\ No newline at end of file
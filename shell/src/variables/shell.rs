@@ -0,0 +1,182 @@
+use crate::exec::job::*;
+use crate::exec::trap::*;
+use crate::history::store::*;
+use crate::parsing::ast::*;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::rc::Rc;
+
+/// A shell variable. Exported variables are mirrored in the process
+/// environment so every program we start sees them.
+#[derive(Debug, Clone)]
+pub struct ShellVar {
+    pub value: String,
+    pub exported: bool,
+}
+
+/// A `break` or `continue` on its way out through the enclosing loops.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Flow {
+    #[default]
+    Normal,
+    // how many more loops to leave
+    Break(usize),
+    // how many loops to leave before continuing the last one
+    Continue(usize),
+    // `return` from the running function
+    Return,
+}
+
+/// State that lives as long as the shell and that commands can read or change.
+#[derive(Default)]
+pub struct Shell {
+    // exit status of the last pipeline, `$?`
+    pub last_status: i32,
+    // status of the last command substitution of the command being expanded
+    pub subst_status: Option<i32>,
+    pub vars: BTreeMap<String, ShellVar>,
+    // `$0`: the script being run, or the shell's own name
+    pub arg0: String,
+    // `$1`, `$2`, ...
+    pub positional: Vec<String>,
+    // commands come from a terminal, so prompts are shown
+    pub interactive: bool,
+    // how many loops the running command is inside of
+    pub loop_depth: usize,
+    // how many `if` and `while` conditions it is inside of, where failing
+    // is no error
+    pub condition_depth: usize,
+    // how many `source` files are running, so `return` can leave them
+    pub source_depth: usize,
+    pub flow: Flow,
+    // alias name to the text it stands for
+    pub aliases: BTreeMap<String, String>,
+    // functions by name
+    pub functions: BTreeMap<String, Rc<Command>>,
+    // one frame per running function call, holding what its `local`
+    // variables hid so it can be put back on return
+    pub locals: Vec<Vec<(String, Option<ShellVar>)>>,
+    // commands typed at the prompt
+    pub history: History,
+    // jobs started with `&` or stopped with Ctrl-Z
+    pub jobs: Jobs,
+    // `$!`: the process of the last job started with `&`
+    pub last_bg_pid: Option<i32>,
+    pub traps: Traps,
+}
+
+/// Whether `name` can be used as a variable name (`[A-Za-z_][A-Za-z0-9_]*`).
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+impl Shell {
+    /// A shell whose variables start as a copy of the process environment.
+    pub fn new() -> Self {
+        let mut shell = Shell { arg0: "0-shell".to_string(), ..Shell::default() };
+        for (name, value) in env::vars() {
+            shell.vars.insert(name, ShellVar { value, exported: true });
+        }
+        shell
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(|v| v.value.as_str())
+    }
+
+    /// Set a variable, keeping its exported flag if it already exists.
+    pub fn set_var(&mut self, name: &str, value: &str) {
+        let exported = self.vars.get(name).is_some_and(|v| v.exported);
+        self.vars.insert(name.to_string(), ShellVar { value: value.to_string(), exported });
+        self.sync_env(name);
+    }
+
+    /// Mark a variable as exported, setting it first when a value is given.
+    pub fn export_var(&mut self, name: &str, value: Option<&str>) {
+        let value = match value {
+            Some(v) => v.to_string(),
+            None => match self.vars.get(name) {
+                Some(v) => v.value.clone(),
+                // nothing to export yet
+                None => return,
+            },
+        };
+        self.vars.insert(name.to_string(), ShellVar { value, exported: true });
+        self.sync_env(name);
+    }
+
+    pub fn unset_var(&mut self, name: &str) {
+        self.vars.remove(name);
+        self.sync_env(name);
+    }
+
+    /// Set variables for the duration of one command (`NAME=value cmd`).
+    /// Returns what is needed to undo it with `restore_vars`.
+    pub fn push_temp_vars(&mut self, assignments: &[(String, String)]) -> Vec<(String, Option<ShellVar>)> {
+        let mut saved = Vec::with_capacity(assignments.len());
+        for (name, value) in assignments {
+            saved.push((name.clone(), self.vars.get(name).cloned()));
+            self.vars.insert(name.clone(), ShellVar { value: value.clone(), exported: true });
+            self.sync_env(name);
+        }
+        saved
+    }
+
+    pub fn restore_vars(&mut self, saved: Vec<(String, Option<ShellVar>)>) {
+        for (name, old) in saved.into_iter().rev() {
+            match old {
+                Some(var) => self.vars.insert(name.clone(), var),
+                None => self.vars.remove(&name),
+            };
+            self.sync_env(&name);
+        }
+    }
+
+    /// Make a variable local to the running function call. Its current value
+    /// comes back when the call returns. Returns false outside a function.
+    pub fn make_local(&mut self, name: &str) -> bool {
+        let old = self.vars.get(name).cloned();
+        let Some(frame) = self.locals.last_mut() else {
+            return false;
+        };
+        if !frame.iter().any(|(n, _)| n == name) {
+            frame.push((name.to_string(), old));
+        }
+        true
+    }
+
+    fn sync_env(&self, name: &str) {
+        // SAFETY: the shell is single threaded, nothing else reads the
+        // environment while it is being changed
+        unsafe {
+            match self.vars.get(name) {
+                Some(var) if var.exported => env::set_var(name, &var.value),
+                _ => env::remove_var(name),
+            }
+        }
+    }
+
+    /// Remember a command typed at the prompt and append it to the history
+    /// file.
+    pub fn add_history(&mut self, source: &str) {
+        let config = HistoryConfig::from_shell(self);
+        let now = chrono::Local::now().timestamp();
+        if let Err(e) = self.history.add(source, now, &config) {
+            let path = config.file.unwrap_or_default();
+            eprintln!("0-shell: {}: {}", path.display(), e);
+        }
+    }
+
+    /// Leave the shell with the given status.
+    pub fn exit(&self, status: i32) -> ! {
+        let _ = io::stdout().flush();
+        process::exit(status)
+    }
+}
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+fn run(script: &str) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_shell")).args(["-c", script]).output().unwrap();
+    (output.status.code().unwrap_or(-1), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn exit_in_the_exit_trap_sets_the_status() {
+    assert_eq!(run("trap 'echo bye; exit 5' EXIT; exit 1"), (5, "bye\n".to_string()));
+}
+
+#[test]
+fn exit_trap_runs_once_at_the_end() {
+    assert_eq!(run("trap 'echo bye' EXIT; echo hi"), (0, "hi\nbye\n".to_string()));
+}